@@ -34,9 +34,44 @@ impl<T: BinRead> EXGeoHashArray<T> {
         self.data.len()
     }
 
+    /// Number of elements as stored in the array header, including those of unit type arrays that aren't read
+    pub fn array_size(&self) -> usize {
+        self.array_size.max(0) as usize
+    }
+
     pub fn data(&self) -> &Vec<T> {
         return &self.data;
     }
+
+    pub fn data_mut(&mut self) -> &mut Vec<T> {
+        &mut self.data
+    }
+
+    /// Moves the elements to a new absolute offset. The relative offset is recalculated when the array is written
+    pub fn set_data_offset_absolute(&mut self, offset_absolute: u64) {
+        self.rel_offset.set_offset_absolute(offset_absolute);
+    }
+}
+
+impl<T: BinRead + BinWrite> EXGeoHashArray<T>
+where
+    for<'a> <T as BinWrite>::Args<'a>: Clone,
+{
+    /// Writes the elements of the array at its data offset, leaving the writer position unchanged
+    pub fn write_data<W: std::io::Write + std::io::Seek>(
+        &self,
+        writer: &mut W,
+        endian: binrw::Endian,
+        args: <T as BinWrite>::Args<'_>,
+    ) -> binrw::BinResult<()> {
+        write_elements(
+            &self.data,
+            self.rel_offset.offset_absolute(),
+            writer,
+            endian,
+            args,
+        )
+    }
 }
 
 impl<T: BinRead, I: SliceIndex<[T]>> Index<I> for EXGeoHashArray<T> {
@@ -82,15 +117,20 @@ where
     }
 }
 
+/// Only writes the array header, use [`EXGeoHashArray::write_data`] to write the elements
 impl<T: BinRead> BinWrite for EXGeoHashArray<T> {
     type Args<'a> = ();
     fn write_options<W: std::io::Write + std::io::Seek>(
         &self,
-        _writer: &mut W,
-        _endian: binrw::Endian,
+        writer: &mut W,
+        endian: binrw::Endian,
         _args: Self::Args<'_>,
     ) -> binrw::BinResult<()> {
-        todo!()
+        let array_size = written_array_size(self.array_size as i32, &self.data) as i16;
+
+        array_size.write_options(writer, endian, ())?;
+        self._hash_size.write_options(writer, endian, ())?;
+        self.rel_offset.write_options(writer, endian, ())
     }
 }
 
@@ -163,6 +203,36 @@ impl<T: BinRead> EXRelArray<T> {
     pub fn data(&self) -> &Vec<T> {
         return &self.data;
     }
+
+    pub fn data_mut(&mut self) -> &mut Vec<T> {
+        &mut self.data
+    }
+
+    /// Moves the elements to a new absolute offset. The relative offset is recalculated when the array is written
+    pub fn set_data_offset_absolute(&mut self, offset_absolute: u64) {
+        self.rel_offset.set_offset_absolute(offset_absolute);
+    }
+}
+
+impl<T: BinRead + BinWrite> EXRelArray<T>
+where
+    for<'a> <T as BinWrite>::Args<'a>: Clone,
+{
+    /// See [`EXGeoHashArray::write_data`]
+    pub fn write_data<W: std::io::Write + std::io::Seek>(
+        &self,
+        writer: &mut W,
+        endian: binrw::Endian,
+        args: <T as BinWrite>::Args<'_>,
+    ) -> binrw::BinResult<()> {
+        write_elements(
+            &self.data,
+            self.rel_offset.offset_absolute(),
+            writer,
+            endian,
+            args,
+        )
+    }
 }
 
 impl<T: BinRead> BinRead for EXRelArray<T>
@@ -198,15 +268,19 @@ where
     }
 }
 
+/// See the [`BinWrite`] implementation of [`EXGeoHashArray`]
 impl<T: BinRead> BinWrite for EXRelArray<T> {
     type Args<'a> = ();
     fn write_options<W: std::io::Write + std::io::Seek>(
         &self,
-        _writer: &mut W,
-        _endian: binrw::Endian,
+        writer: &mut W,
+        endian: binrw::Endian,
         _args: Self::Args<'_>,
     ) -> binrw::BinResult<()> {
-        todo!()
+        let array_size = written_array_size(self.array_size, &self.data);
+
+        array_size.write_options(writer, endian, ())?;
+        self.rel_offset.write_options(writer, endian, ())
     }
 }
 
@@ -244,6 +318,42 @@ impl<T: BinRead + Serialize> Serialize for EXRelArray<T> {
     }
 }
 
+/// Writes array elements starting at `offset`, leaving the writer position unchanged
+fn write_elements<T: BinWrite, W: std::io::Write + std::io::Seek>(
+    data: &[T],
+    offset: u64,
+    writer: &mut W,
+    endian: binrw::Endian,
+    args: <T as BinWrite>::Args<'_>,
+) -> binrw::BinResult<()>
+where
+    for<'a> <T as BinWrite>::Args<'a>: Clone,
+{
+    if data.is_empty() {
+        return Ok(());
+    }
+
+    let pos_saved = writer.stream_position()?;
+    writer.seek(std::io::SeekFrom::Start(offset))?;
+
+    for v in data {
+        v.write_options(writer, endian, args.clone())?;
+    }
+
+    writer.seek(std::io::SeekFrom::Start(pos_saved))?;
+
+    Ok(())
+}
+
+/// Size to write in an array header. Arrays of unit types don't store their data, so the original size is kept
+fn written_array_size<T>(original: i32, data: &[T]) -> i32 {
+    if size_of::<T>() == 0 {
+        original
+    } else {
+        data.len() as i32
+    }
+}
+
 #[binrw]
 #[derive(Debug, Clone)]
 pub struct EXGeoCommonArrayElement {
@@ -41,6 +41,11 @@ impl<T: BinRead, OT: BinRead + NumCast + NumSize, const OFFSET: i64> EXRelPtr<T,
     pub fn data(self) -> T {
         self.data
     }
+
+    /// Points the pointer to a new absolute offset. The relative offset is recalculated when the pointer is written
+    pub fn set_offset_absolute(&mut self, offset_absolute: u64) {
+        self.offset_absolute = offset_absolute;
+    }
 }
 
 impl<'a, T: BinRead, OT: BinRead + NumCast + NumSize, const OFFSET: i64> BinRead
@@ -82,18 +87,33 @@ where
     }
 }
 
-impl<T: BinRead, OT: BinRead + NumCast + NumSize, const OFFSET: i64> BinWrite
+/// Only writes the pointer itself, recalculated from the absolute offset. The data it points to is not written
+impl<T: BinRead, OT: BinRead + BinWrite + NumCast + NumSize, const OFFSET: i64> BinWrite
     for EXRelPtr<T, OT, OFFSET>
+where
+    for<'a> <OT as BinWrite>::Args<'a>: Default,
 {
     type Args<'a> = ();
 
     fn write_options<W: std::io::Write + std::io::Seek>(
         &self,
-        _writer: &mut W,
-        _endian: binrw::Endian,
+        writer: &mut W,
+        endian: binrw::Endian,
         _args: Self::Args<'_>,
     ) -> binrw::BinResult<()> {
-        todo!()
+        let pos = writer.stream_position()?;
+        let offset_relative = self.offset_absolute as i64 - pos as i64 - OFFSET;
+        let offset: OT =
+            NumCast::from(offset_relative).ok_or_else(|| binrw::Error::AssertFail {
+                pos,
+                message: format!(
+                    "Relative offset {offset_relative} to 0x{:x} doesn't fit in {} bytes",
+                    self.offset_absolute,
+                    OT::size_bytes()
+                ),
+            })?;
+
+        offset.write_options(writer, endian, Default::default())
     }
 }
 
@@ -1,51 +1,26 @@
-use std::{
-    io::{Read, Seek},
-    mem::transmute,
-};
+use std::io::{Read, Seek};
 
 use crate::{
     binrw::{BinReaderExt, Endian},
     header::EXGeoHeader,
-    versions::Platform,
+    references::CollectorScope,
+    versions::{GameProfile, Platform},
     Hashcode, HashcodeUtils,
 };
 use tracing::{info, warn};
 
 use crate::error::Result;
 
-pub trait DatabaseReader: Read + Seek {
-    fn downcast_to_edbfile(&mut self) -> Option<&mut EdbFile>;
-}
+/// Object safe combination of [`Read`] and [`Seek`] for the boxed reader
+trait EdbReader: Read + Seek {}
 
-impl<R: Read + Seek + Sized> DatabaseReader for R {
-    fn downcast_to_edbfile(&mut self) -> Option<&mut EdbFile> {
-        // Safety: as long as the safety marker is present, we are good to downcast
-        unsafe {
-            let ptr: *mut EdbFile = transmute(self as *mut _);
-
-            // Check alignment and safety marker
-            if (transmute::<_, usize>(ptr) & 0x7) == 0
-                && (*ptr).safety_marker == EdbFile::SAFETY_MARKER
-            {
-                Some(transmute(ptr))
-            } else {
-                if cfg!(debug_assertions) {
-                    warn!("Couldn't verify EdbFile marker and alignment!");
-                }
-
-                None
-            }
-        }
-    }
-}
+impl<R: Read + Seek> EdbReader for R {}
 
 pub struct EdbFile {
-    /// Using a marker to allow for safe downcasting when access to the object is needed in
-    safety_marker: u64,
-
-    reader: Box<dyn DatabaseReader>,
+    reader: Box<dyn EdbReader>,
     pub endian: Endian,
     pub platform: Platform,
+    pub profile: GameProfile,
     pub header: EXGeoHeader,
 
     /// External hashcodes used by loaded objects
@@ -56,11 +31,18 @@ pub struct EdbFile {
 }
 
 impl EdbFile {
-    pub const SAFETY_MARKER: u64 = 0xDEADC0FF47654F6D;
-
     /// Resets the reader, tests endianness and reads the header
-    pub fn new(reader: Box<dyn DatabaseReader>, platform: Platform) -> Result<Self> {
-        let mut reader = reader;
+    pub fn new<R: Read + Seek + 'static>(reader: R, platform: Platform) -> Result<Self> {
+        Self::with_game(reader, platform, None)
+    }
+
+    /// Same as [`EdbFile::new`], using the game ID from the filelist path (eg. 'gforce') to help pick the game profile
+    pub fn with_game<R: Read + Seek + 'static>(
+        reader: R,
+        platform: Platform,
+        game_id: Option<&str>,
+    ) -> Result<Self> {
+        let mut reader: Box<dyn EdbReader> = Box::new(reader);
         reader.seek(std::io::SeekFrom::Start(0)).ok();
         let endian = if reader.read_ne::<u8>()? == 0x47 {
             Endian::Big
@@ -70,6 +52,7 @@ impl EdbFile {
 
         reader.seek(std::io::SeekFrom::Start(8))?;
         let version = reader.read_type::<u32>(endian)?;
+        let flags = reader.read_type::<u32>(endian)?;
         if version >= 0x10000 {
             return Err(crate::error::EurochefError::Unsupported(
                 crate::error::UnsupportedError::EngineXT(version),
@@ -82,24 +65,27 @@ impl EdbFile {
             ));
         }
 
+        let profile = GameProfile::detect(version, flags, game_id);
+
         reader.seek(std::io::SeekFrom::Start(0))?;
 
-        let header = reader.read_type::<EXGeoHeader>(endian)?;
+        let header = reader.read_type_args::<EXGeoHeader>(endian, (profile,))?;
 
         info!(
-            "Loaded EDB {:08x} v{} (build date {}, size 0x{:x}, platform {})",
+            "Loaded EDB {:08x} v{} ({}, build date {}, size 0x{:x}, platform {})",
             header.hashcode,
             header.version,
+            profile.name,
             chrono::NaiveDateTime::from_timestamp_opt(header.time as _, 0).unwrap(),
             header.file_size,
             platform
         );
 
         Ok(Self {
-            safety_marker: Self::SAFETY_MARKER,
             reader,
             endian,
             platform,
+            profile,
             header,
             external_references: vec![],
             internal_references: vec![],
@@ -121,6 +107,28 @@ impl EdbFile {
             self.internal_references.push(reference);
         }
     }
+
+    /// Runs `f`, adding the references recorded by every object parsed inside of it to this file
+    ///
+    /// See [`crate::references`]
+    pub fn collect_references<T>(&mut self, f: impl FnOnce(&mut Self) -> T) -> T {
+        let scope = CollectorScope::begin();
+        let result = f(self);
+        let collector = scope.finish();
+
+        for (file, reference) in collector.references {
+            self.add_reference(file, reference);
+        }
+
+        for index in collector.texture_indices {
+            match self.header.texture_list.data().get(index as usize) {
+                Some(t) => self.add_reference_internal(t.common.hashcode),
+                None => warn!("Texture index {index} is out of bounds"),
+            }
+        }
+
+        result
+    }
 }
 
 impl Seek for EdbFile {
@@ -5,6 +5,7 @@ use serde::Serialize;
 use crate::{
     common::{EXRelPtr, EXVector},
     entity_mesh::EXGeoMeshEntity,
+    references::record_texture_index,
     versions::Platform,
 };
 
@@ -121,6 +122,7 @@ pub struct EXGeoEntity_TextureList {
     pub texture_count: u16,
 
     #[br(count = texture_count)]
+    #[br(map = |x: Vec<u16>| { x.iter().for_each(|t| record_texture_index(*t)); x })]
     pub textures: Vec<u16>,
 }
 
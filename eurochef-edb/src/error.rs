@@ -13,6 +13,9 @@ pub enum EurochefError {
     #[error("BinRW error")]
     BinRw(#[from] binrw::Error),
 
+    #[error("Invalid refpointer {0}: {1}")]
+    InvalidRefPointer(usize, String),
+
     #[error("Error")]
     Misc(#[from] anyhow::Error),
 }
@@ -9,6 +9,7 @@ use crate::{
         EXGeoEntityHeader, EXGeoSpreadSheetHeader,
     },
     texture::EXGeoTextureHeader,
+    versions::GameProfile,
     // versions::{EDB_VERSION_BOND, EDB_VERSION_GFORCE, EDB_VERSION_ICEAGE3},
 };
 
@@ -21,7 +22,7 @@ pub type EXGeoFontHeader = EXGeoCommonArrayElement;
 pub type EXGeoMaterialHeader = EXGeoCommonArrayElement;
 
 #[binrw]
-#[brw(magic = 0x47454F4Du32)]
+#[brw(magic = 0x47454F4Du32, import(profile: GameProfile))]
 #[derive(Debug, Clone)]
 pub struct EXGeoHeader {
     pub hashcode: u32,
@@ -57,8 +58,7 @@ pub struct EXGeoHeader {
     #[brw(if(version.ge(&248)))]
     pub material_list: EXGeoHashArray<EXGeoMaterialHeader>, // 0xb0
 
-    // ! Spyro Hack
-    #[brw(if(version.eq(&240)))]
+    #[brw(if(profile.header_texture_list_pad))]
     _spyrohack: u64,
 
     pub texture_list: EXGeoHashArray<EXGeoTextureHeader>,
@@ -66,4 +66,99 @@ pub struct EXGeoHeader {
     pub unk_c0: EXRelArray<()>,
 }
 
+impl EXGeoHeader {
+    /// Addresses of the element data of every array in the header
+    pub fn array_addresses(&self) -> Vec<u32> {
+        [
+            self.section_list.data_offset_absolute(),
+            self.refpointer_list.data_offset_absolute(),
+            self.entity_list.data_offset_absolute(),
+            self.anim_list.data_offset_absolute(),
+            self.animskin_list.data_offset_absolute(),
+            self.animscript_list.data_offset_absolute(),
+            self.map_list.data_offset_absolute(),
+            self.animmode_list.data_offset_absolute(),
+            self.animset_list.data_offset_absolute(),
+            self.particle_list.data_offset_absolute(),
+            self.swoosh_list.data_offset_absolute(),
+            self.spreadsheet_list.data_offset_absolute(),
+            self.font_list.data_offset_absolute(),
+            self.material_list.data_offset_absolute(),
+            self.texture_list.data_offset_absolute(),
+        ]
+        .into_iter()
+        .map(|a| a as u32)
+        .collect()
+    }
+
+    /// Array elements of every object listed in the header, excluding sections and refpointers
+    ///
+    /// The order matches [`EXGeoHeader::object_elements_mut`]
+    pub fn object_elements(&self) -> Vec<&EXGeoCommonArrayElement> {
+        let mut elements: Vec<&EXGeoCommonArrayElement> = vec![];
+        elements.extend(self.entity_list.iter().map(|e| &e.common));
+        elements.extend(self.anim_list.iter().map(|e| &e.common));
+        elements.extend(self.animskin_list.iter().map(|e| &e.common));
+        elements.extend(self.animscript_list.iter());
+        elements.extend(self.map_list.iter());
+        elements.extend(self.animmode_list.iter().map(|e| &e.common));
+        elements.extend(self.animset_list.iter().map(|e| &e.common));
+        elements.extend(self.particle_list.iter());
+        elements.extend(self.swoosh_list.iter());
+        elements.extend(self.spreadsheet_list.iter().map(|e| &e.common));
+        elements.extend(self.font_list.iter());
+        elements.extend(self.material_list.iter());
+        elements.extend(self.texture_list.iter().map(|e| &e.common));
+        elements
+    }
+
+    pub fn object_elements_mut(&mut self) -> Vec<&mut EXGeoCommonArrayElement> {
+        let mut elements: Vec<&mut EXGeoCommonArrayElement> = vec![];
+        elements.extend(
+            self.entity_list
+                .data_mut()
+                .iter_mut()
+                .map(|e| &mut e.common),
+        );
+        elements.extend(self.anim_list.data_mut().iter_mut().map(|e| &mut e.common));
+        elements.extend(
+            self.animskin_list
+                .data_mut()
+                .iter_mut()
+                .map(|e| &mut e.common),
+        );
+        elements.extend(self.animscript_list.data_mut().iter_mut());
+        elements.extend(self.map_list.data_mut().iter_mut());
+        elements.extend(
+            self.animmode_list
+                .data_mut()
+                .iter_mut()
+                .map(|e| &mut e.common),
+        );
+        elements.extend(
+            self.animset_list
+                .data_mut()
+                .iter_mut()
+                .map(|e| &mut e.common),
+        );
+        elements.extend(self.particle_list.data_mut().iter_mut());
+        elements.extend(self.swoosh_list.data_mut().iter_mut());
+        elements.extend(
+            self.spreadsheet_list
+                .data_mut()
+                .iter_mut()
+                .map(|e| &mut e.common),
+        );
+        elements.extend(self.font_list.data_mut().iter_mut());
+        elements.extend(self.material_list.data_mut().iter_mut());
+        elements.extend(
+            self.texture_list
+                .data_mut()
+                .iter_mut()
+                .map(|e| &mut e.common),
+        );
+        elements
+    }
+}
+
 // structure_size_tests!(EXGeoHeader = 936);
@@ -7,11 +7,16 @@ pub mod entity_mesh;
 pub mod error;
 pub mod header;
 pub mod map;
+pub mod references;
+pub mod refpointer;
 pub mod script;
+#[cfg(test)]
+mod test_util;
 pub mod text;
 pub mod texture;
 pub mod util;
 pub mod versions;
+pub mod writer;
 
 // Re-export binrw
 pub use binrw;
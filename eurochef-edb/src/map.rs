@@ -4,14 +4,15 @@ use serde::Serialize;
 use crate::{
     array::{EXGeoHashArray, EXRelArray},
     common::{EXRelPtr, EXVector, EXVector2, EXVector3},
-    edb::DatabaseReader,
+    references::{record_reference, record_reference_internal},
     util::BitExtensions,
+    versions::GameProfile,
     Hashcode,
 };
 
 #[binrw]
 #[derive(Debug, Serialize, Clone)]
-#[brw(import(version: u32))] // TODO: Seems a bit dirty, no?
+#[brw(import(version: u32, profile: GameProfile))] // TODO: Seems a bit dirty, no?
 pub struct EXGeoMap {
     #[brw(assert(common.eq(&0x500)))]
     pub common: u32,
@@ -36,19 +37,19 @@ pub struct EXGeoMap {
     #[serde(skip)]
     num_zones: u32, // 0x84
 
-    #[brw(if(version.eq(&205)))]
+    #[brw(if(profile.short_map_zones))]
     _unk_zonepad: [u32; 6],
 
     #[br(args {
         count: num_zones as usize,
-        inner: (version,)
+        inner: (version, profile)
     })]
     pub zones: Vec<EXGeoMapZone>, // 0x88
 }
 
 #[binrw]
 #[derive(Debug, Serialize, Clone)]
-#[br(import(version: u32))]
+#[br(import(version: u32, profile: GameProfile))]
 // TODO(cohae): Struct is not accurate below version 248 yet
 pub struct EXGeoMapZone {
     pub entity_refptr: u32,                    // 0x0
@@ -56,11 +57,11 @@ pub struct EXGeoMapZone {
     // TODO(cohae): Inaccurate big time
     pub light_array: EXGeoHashArray<()>, // 0x8
     pub sound_array: EXGeoHashArray<()>, // 0x10
-    #[br(if(version.ne(&205)))]
+    #[br(if(!profile.short_map_zones))]
     pub unk18: Option<EXRelArray<()>>, // ???, 0x18 (u16?)
-    #[br(if(version.ne(&205)))]
+    #[br(if(!profile.short_map_zones))]
     pub unk20: Option<EXRelArray<()>>, // ???, 0x20
-    #[br(if(version.ne(&205)))]
+    #[br(if(!profile.short_map_zones))]
     pub unk28: Option<EXRelPtr<()>>, // PlacementInfo?, 0x28
     pub unk2c: EXRelPtr<()>,             // ???, 0x2c
     pub hash_ref: u32,                   // 0x30
@@ -253,11 +254,9 @@ impl BinRead for EXGeoTriggerEngineOptions {
             res._unk7 = Some(reader.read_type(endian)?);
         }
 
-        if let Some(edb) = reader.downcast_to_edbfile() {
-            if let Some(visual_hashcode) = res.visual_object {
-                if let Some(file_hashcode) = res.visual_object_file {
-                    edb.add_reference(file_hashcode, visual_hashcode)
-                }
+        if let Some(visual_hashcode) = res.visual_object {
+            if let Some(file_hashcode) = res.visual_object_file {
+                record_reference(file_hashcode, visual_hashcode)
             }
         }
 
@@ -359,6 +358,7 @@ pub struct EXGeoPlacement {
     pub scale: EXVector3,
     pub engine_flags: u16,
     pub map_on: u16,
+    #[br(map = |x: u32| { record_reference_internal(x); x })]
     pub object_ref: u32,
     pub light_set: u16,
     pub group: i16,
@@ -0,0 +1,103 @@
+//! Collection of references made by objects while they are being parsed
+//!
+//! binrw parsers don't have access to the [`EdbFile`](crate::edb::EdbFile) they are reading from, so references are
+//! recorded into a thread-local collector instead. The collector only exists for the duration of
+//! [`EdbFile::collect_references`](crate::edb::EdbFile::collect_references), recording outside of it does nothing.
+
+use std::cell::RefCell;
+
+use crate::Hashcode;
+
+thread_local! {
+    static COLLECTOR: RefCell<Option<ReferenceCollector>> = const { RefCell::new(None) };
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct ReferenceCollector {
+    /// (file, object) hashcode pairs, including references to objects in the same file
+    pub references: Vec<(Hashcode, Hashcode)>,
+
+    /// Indices into the texture list of the current file, resolved to hashcodes when the scope ends
+    pub texture_indices: Vec<u16>,
+}
+
+/// Records a reference to an object in another file. Files with hashcode `u32::MAX` refer to the current file
+pub fn record_reference(file: Hashcode, reference: Hashcode) {
+    COLLECTOR.with(|c| {
+        if let Some(collector) = c.borrow_mut().as_mut() {
+            collector.references.push((file, reference));
+        }
+    });
+}
+
+/// Records a reference to an object in the current file
+pub fn record_reference_internal(reference: Hashcode) {
+    record_reference(u32::MAX, reference);
+}
+
+/// Records a reference to a texture in the current file by its index in the texture list
+pub fn record_texture_index(index: u16) {
+    COLLECTOR.with(|c| {
+        if let Some(collector) = c.borrow_mut().as_mut() {
+            collector.texture_indices.push(index);
+        }
+    });
+}
+
+/// Restores the previous collector when dropped, so scopes can be nested and survive panics
+pub(crate) struct CollectorScope {
+    /// Collector of the enclosing scope, taken once this scope is finished
+    previous: Option<Option<ReferenceCollector>>,
+}
+
+impl CollectorScope {
+    pub(crate) fn begin() -> Self {
+        let previous = COLLECTOR.with(|c| c.replace(Some(ReferenceCollector::default())));
+        Self {
+            previous: Some(previous),
+        }
+    }
+
+    /// Ends the scope, returning the references that were recorded in it
+    pub(crate) fn finish(mut self) -> ReferenceCollector {
+        let previous = self.previous.take().flatten();
+        COLLECTOR.with(|c| c.replace(previous)).unwrap_or_default()
+    }
+}
+
+impl Drop for CollectorScope {
+    fn drop(&mut self) {
+        // Only does anything if the scope wasn't finished, eg. when unwinding
+        if let Some(previous) = self.previous.take() {
+            COLLECTOR.with(|c| c.replace(previous));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nested_scopes() {
+        record_reference_internal(1);
+
+        let outer = CollectorScope::begin();
+        record_reference(0x10, 2);
+
+        let inner = CollectorScope::begin();
+        record_texture_index(3);
+        let inner = inner.finish();
+
+        record_reference_internal(4);
+        let outer = outer.finish();
+
+        assert_eq!(inner.references, []);
+        assert_eq!(inner.texture_indices, [3]);
+        assert_eq!(outer.references, [(0x10, 2), (u32::MAX, 4)]);
+        assert!(outer.texture_indices.is_empty());
+
+        // Nothing is recorded outside of a scope
+        assert!(COLLECTOR.with(|c| c.borrow().is_none()));
+    }
+}
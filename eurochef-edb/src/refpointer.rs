@@ -0,0 +1,280 @@
+use std::io::{Seek, SeekFrom};
+
+use binrw::BinReaderExt;
+use serde::Serialize;
+use tracing::warn;
+
+use crate::{
+    edb::EdbFile,
+    entity::EXGeoEntity,
+    error::{EurochefError, Result},
+    header::EXGeoHeader,
+    Hashcode,
+};
+
+/// Kind of object a refpointer points to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum EXObjectKind {
+    Entity,
+    Anim,
+    AnimSkin,
+    AnimScript,
+    Map,
+    AnimMode,
+    AnimSet,
+    Particle,
+    Swoosh,
+    Spreadsheet,
+    Font,
+    Material,
+    Texture,
+    Unknown,
+}
+
+impl EXObjectKind {
+    /// Finds the header array listing an object at `address`
+    pub fn from_header(header: &EXGeoHeader, address: u32) -> Option<Self> {
+        let lists: [(Self, Box<dyn Iterator<Item = u32> + '_>); 13] = [
+            (
+                Self::Entity,
+                Box::new(header.entity_list.iter().map(|e| e.common.address)),
+            ),
+            (
+                Self::Anim,
+                Box::new(header.anim_list.iter().map(|e| e.common.address)),
+            ),
+            (
+                Self::AnimSkin,
+                Box::new(header.animskin_list.iter().map(|e| e.common.address)),
+            ),
+            (
+                Self::AnimScript,
+                Box::new(header.animscript_list.iter().map(|e| e.address)),
+            ),
+            (
+                Self::Map,
+                Box::new(header.map_list.iter().map(|e| e.address)),
+            ),
+            (
+                Self::AnimMode,
+                Box::new(header.animmode_list.iter().map(|e| e.common.address)),
+            ),
+            (
+                Self::AnimSet,
+                Box::new(header.animset_list.iter().map(|e| e.common.address)),
+            ),
+            (
+                Self::Particle,
+                Box::new(header.particle_list.iter().map(|e| e.address)),
+            ),
+            (
+                Self::Swoosh,
+                Box::new(header.swoosh_list.iter().map(|e| e.address)),
+            ),
+            (
+                Self::Spreadsheet,
+                Box::new(header.spreadsheet_list.iter().map(|e| e.common.address)),
+            ),
+            (
+                Self::Font,
+                Box::new(header.font_list.iter().map(|e| e.address)),
+            ),
+            (
+                Self::Material,
+                Box::new(header.material_list.iter().map(|e| e.address)),
+            ),
+            (
+                Self::Texture,
+                Box::new(header.texture_list.iter().map(|e| e.common.address)),
+            ),
+        ];
+
+        lists
+            .into_iter()
+            .find_map(|(kind, mut addresses)| addresses.any(|a| a == address).then_some(kind))
+    }
+
+    /// Derives the kind from the type code an object starts with. Only used for objects that aren't listed in the
+    /// header (eg. entities inside of split entities), so only the known type codes of those are matched
+    pub fn from_type_code(type_code: u32) -> Self {
+        match type_code {
+            0x601..=0x603 => Self::Entity,
+            0x300 => Self::AnimScript,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+/// A decoded entry of the refpointer list
+///
+/// The layout of section list elements isn't known, so the section hashcode and the file owning the object aren't
+/// decoded, only the section index is
+#[derive(Debug, Clone, Serialize)]
+pub struct EXGeoRefPointer {
+    pub index: usize,
+    pub hashcode: Hashcode,
+
+    /// Index of the section the object is located in
+    pub section: u16,
+
+    /// Absolute address of the object in the file containing the refpointer list
+    pub address: u32,
+    pub type_code: u32,
+    pub kind: EXObjectKind,
+    /// Whether the object is listed in one of the header arrays, or only reachable through refpointers
+    pub listed: bool,
+}
+
+impl EdbFile {
+    /// Decodes a refpointer, checking that it points to an address inside of the file
+    ///
+    /// Section indices past the end of the section list only produce a warning, as they're used by refpointers of
+    /// valid objects (eg. map zones and spreadsheets)
+    pub fn refpointer(&mut self, index: usize) -> Result<EXGeoRefPointer> {
+        let invalid = |reason: String| EurochefError::InvalidRefPointer(index, reason);
+
+        let r = self
+            .header
+            .refpointer_list
+            .data()
+            .get(index)
+            .ok_or_else(|| {
+                invalid(format!(
+                    "out of bounds (file has {} refpointers)",
+                    self.header.refpointer_list.len()
+                ))
+            })?
+            .clone();
+
+        let section_count = self.header.section_list.array_size();
+        if section_count != 0 && r.section as usize >= section_count {
+            warn!(
+                "Refpointer {index} points to section {}, but the file has {section_count} sections",
+                r.section
+            );
+        }
+
+        if r.address >= self.header.file_size {
+            return Err(invalid(format!(
+                "address 0x{:x} is outside of the file",
+                r.address
+            )));
+        }
+
+        self.seek(SeekFrom::Start(r.address as u64))?;
+        let type_code = self.read_type::<u32>(self.endian)?;
+        let listed_kind = EXObjectKind::from_header(&self.header, r.address);
+
+        Ok(EXGeoRefPointer {
+            index,
+            hashcode: r.hashcode,
+            section: r.section,
+            address: r.address,
+            type_code,
+            kind: listed_kind.unwrap_or_else(|| EXObjectKind::from_type_code(type_code)),
+            listed: listed_kind.is_some(),
+        })
+    }
+
+    /// Decodes every refpointer. Returns an error on the first invalid one
+    pub fn refpointers(&mut self) -> Result<Vec<EXGeoRefPointer>> {
+        (0..self.header.refpointer_list.len())
+            .map(|i| self.refpointer(i))
+            .collect()
+    }
+
+    /// Returns every invalid refpointer along with the reason it is invalid
+    pub fn validate_refpointers(&mut self) -> Vec<EurochefError> {
+        (0..self.header.refpointer_list.len())
+            .filter_map(|i| self.refpointer(i).err())
+            .collect()
+    }
+
+    /// Reads the entity a refpointer points to
+    pub fn read_refpointer_entity(&mut self, index: usize) -> Result<EXGeoEntity> {
+        let r = self.refpointer(index)?;
+        if r.kind != EXObjectKind::Entity {
+            return Err(EurochefError::InvalidRefPointer(
+                index,
+                format!("expected an entity, found type 0x{:x}", r.type_code),
+            ));
+        }
+
+        self.seek(SeekFrom::Start(r.address as u64))?;
+        Ok(self.collect_references(|edb| {
+            edb.read_type_args(edb.endian, (edb.header.version, edb.platform))
+        })?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{test_util::TestFile, versions::Platform};
+
+    /// Builds a little endian version 252 file with 1 section, 1 map and 4 refpointers
+    fn test_file() -> EdbFile {
+        let mut file = TestFile::new(0x200, 0x200);
+
+        file.array(0, 1, 0x1c0);
+        file.array(1, 4, 0x100);
+        for (i, (hashcode, section, address)) in [
+            (0x1c000000, 0, 0x140),
+            (0x80000001, 0, 0x160),
+            (0x80000002, 5, 0x160),
+            (0x80000003, 0, 0x300),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            file.element(0x100 + i * 0x10, hashcode, section, address);
+        }
+
+        // The map list is the 7th array
+        file.array(6, 1, 0x1a0);
+        file.element(0x1a0, 0x1c000000, 0, 0x140);
+
+        file.put(0x160, 0x601);
+
+        EdbFile::new(Cursor::new(file.data), Platform::Pc).unwrap()
+    }
+
+    #[test]
+    fn resolve_kinds() {
+        let mut edb = test_file();
+
+        let map = edb.refpointer(0).unwrap();
+        assert_eq!(map.kind, EXObjectKind::Map);
+        assert!(map.listed);
+
+        let entity = edb.refpointer(1).unwrap();
+        assert_eq!(entity.hashcode, 0x80000001);
+        assert_eq!(entity.type_code, 0x601);
+        assert_eq!(entity.kind, EXObjectKind::Entity);
+        assert!(!entity.listed);
+    }
+
+    #[test]
+    fn invalid_refpointers() {
+        let mut edb = test_file();
+
+        let invalid: Vec<usize> = edb
+            .validate_refpointers()
+            .into_iter()
+            .map(|e| match e {
+                EurochefError::InvalidRefPointer(index, _) => index,
+                e => panic!("unexpected error {e}"),
+            })
+            .collect();
+        assert_eq!(invalid, [3]);
+
+        // Unknown sections are only warned about
+        assert_eq!(edb.refpointer(2).unwrap().section, 5);
+
+        assert!(edb.refpointer(4).is_err());
+        assert!(edb.refpointers().is_err());
+        assert!(edb.read_refpointer_entity(0).is_err());
+    }
+}
@@ -1,5 +1,5 @@
 use binrw::{binread, BinRead, BinReaderExt, BinResult, VecArgs};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use tracing::{debug, warn};
 
 use crate::common::{EXRelPtr, EXVector, EXVector3};
@@ -26,7 +26,7 @@ pub struct EXGeoAnimScript {
     pub commands: Vec<EXGeoAnimScriptCmd>,
 }
 
-#[derive(Debug, Serialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct EXGeoAnimScriptControllerHeader {
     pub controller_count: u16,
     pub channel_count: u16,
@@ -36,7 +36,7 @@ pub struct EXGeoAnimScriptControllerHeader {
     pub channels: EXGeoAnimScriptControllerChannels,
 }
 
-#[derive(Debug, Serialize, Clone, Default)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct EXGeoAnimScriptControllerChannels {
     pub time_0: Vec<(f32, f32)>,         // 0x1
     pub time_1: Vec<(f32, f32)>,         // 0x2
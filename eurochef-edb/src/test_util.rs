@@ -0,0 +1,40 @@
+//! Minimal EDB files for unit tests
+
+/// Little endian version 252 file, built by writing the header fields used by a test
+pub struct TestFile {
+    pub data: Vec<u8>,
+}
+
+impl TestFile {
+    pub fn new(file_size: u32, base_file_size: u32) -> Self {
+        let mut file = Self {
+            data: vec![0u8; file_size as usize],
+        };
+
+        file.put(0x0, 0x47454F4D);
+        file.put(0x4, 0x1d000001);
+        file.put(0x8, 252);
+        file.put(0x14, file_size);
+        file.put(0x18, base_file_size);
+
+        file
+    }
+
+    pub fn put(&mut self, offset: usize, value: u32) {
+        self.data[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+    }
+
+    /// Sets the size and element offset of the `index`th array in the header, starting with the section list at 0x40
+    pub fn array(&mut self, index: usize, size: u32, offset: u32) {
+        let header_offset = 0x40 + index * 8;
+        self.put(header_offset, size);
+        self.put(header_offset + 4, offset - header_offset as u32 - 4);
+    }
+
+    /// Writes an array element with a hashcode, section and address at `offset`
+    pub fn element(&mut self, offset: usize, hashcode: u32, section: u16, address: u32) {
+        self.put(offset, hashcode);
+        self.put(offset + 4, section as u32);
+        self.put(offset + 8, address);
+    }
+}
@@ -1,6 +1,11 @@
 use binrw::binrw;
 
-use crate::{array::EXGeoCommonArrayElement, common::EXRelPtr, versions::Platform};
+use crate::{
+    array::EXGeoCommonArrayElement,
+    common::EXRelPtr,
+    references::record_reference,
+    versions::{GameProfile, Platform},
+};
 
 #[binrw]
 #[derive(Debug, Clone)]
@@ -14,7 +19,7 @@ pub struct EXGeoTextureHeader {
 
 #[binrw]
 #[derive(Debug)]
-#[brw(import(version: u32, platform: Platform))]
+#[brw(import(version: u32, platform: Platform, profile: GameProfile))]
 pub struct EXGeoTexture {
     #[brw(if(version <= 205))]
     unk0: u32,
@@ -54,8 +59,8 @@ pub struct EXGeoTexture {
     // #[brw(if(platform == Platform::Ps2 && (version <= 163 || version == 213)))]
     _unk2: u32, // 0x24
 
-    // ! FIXME(cohae): Robots hack, this is the same as the above field, check if this works on other platforms and surrounding versions
-    #[brw(if(version == 248))]
+    // FIXME(cohae): Robots hack, this is the same as the above field, check if this works on other platforms and surrounding versions
+    #[brw(if(profile.texture_extra_pad))]
     _unk2_rbts: u32,
 
     #[brw(if(platform == Platform::Ps2))]
@@ -67,5 +72,11 @@ pub struct EXGeoTexture {
     pub data_size: Option<u32>,
 
     #[br(count = image_count)]
+    #[br(map = |x: Vec<EXRelPtr>| {
+        if let (Some(file), Some(texture)) = (external_file, x.first()) {
+            record_reference(file, texture.offset_relative() as u32);
+        }
+        x
+    })]
     pub frame_offsets: Vec<EXRelPtr>,
 }
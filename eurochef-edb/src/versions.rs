@@ -10,6 +10,111 @@ pub const EDB_VERSION_GFORCE: u32 = 259;
 pub const EDB_VERSION_ICEAGE3: u32 = 260;
 pub const EDB_VERSION_BOND: u32 = 263;
 
+/// Per-game layout overrides for games whose structures can't be told apart by version alone
+///
+/// Profiles are detected with [`GameProfile::detect`]. Versions without a known profile get a generic profile without overrides.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GameProfile {
+    pub name: &'static str,
+    pub version: u32,
+
+    /// Game IDs used as the game directory in filelist paths, eg. 'gforce', 'potccg'
+    pub game_ids: &'static [&'static str],
+    /// Header flags identifying this game as `(mask, value)`, matched as `flags & mask == value`
+    pub header_flags: Option<(u32, u32)>,
+
+    /// 8 extra bytes before the texture list in the header
+    pub header_texture_list_pad: bool,
+    /// Extra u32 after the texture data pointers
+    pub texture_extra_pad: bool,
+    /// Map zones are preceded by 0x18 bytes of padding and lack the 0x18-0x2c fields
+    pub short_map_zones: bool,
+}
+
+impl GameProfile {
+    const fn new(name: &'static str, version: u32, game_ids: &'static [&'static str]) -> Self {
+        Self {
+            name,
+            version,
+            game_ids,
+            header_flags: None,
+            header_texture_list_pad: false,
+            texture_extra_pad: false,
+            short_map_zones: false,
+        }
+    }
+
+    /// Profile for versions without a known game
+    pub const fn generic(version: u32) -> Self {
+        Self::new("Unknown", version, &[])
+    }
+
+    /// Detects the profile from the header version and flags, and optionally the game ID from the filelist path
+    ///
+    /// Profiles with a matching version are picked in order of preference by game ID, then by header flags, then by their
+    /// order in [`GAME_PROFILES`]
+    pub fn detect(version: u32, flags: u32, game_id: Option<&str>) -> Self {
+        Self::detect_in(GAME_PROFILES, version, flags, game_id)
+    }
+
+    fn detect_in(
+        profiles: &[GameProfile],
+        version: u32,
+        flags: u32,
+        game_id: Option<&str>,
+    ) -> Self {
+        let mut candidates = profiles.iter().filter(|p| p.version == version);
+
+        if let Some(game_id) = game_id {
+            if let Some(profile) = candidates
+                .clone()
+                .find(|p| p.game_ids.iter().any(|id| id.eq_ignore_ascii_case(game_id)))
+            {
+                return *profile;
+            }
+        }
+
+        if let Some(profile) = candidates
+            .clone()
+            .find(|p| matches!(p.header_flags, Some((mask, value)) if flags & mask == value))
+        {
+            return *profile;
+        }
+
+        candidates
+            .next()
+            .copied()
+            .unwrap_or_else(|| Self::generic(version))
+    }
+}
+
+pub const GAME_PROFILES: &[GameProfile] = &[
+    GameProfile::new("Sphinx and the Cursed Mummy", 182, &[]),
+    GameProfile {
+        short_map_zones: true,
+        ..GameProfile::new("Unknown (v205)", 205, &[])
+    },
+    GameProfile::new("Spyro: A Hero's Tail Demo", EDB_VERSION_SPYRO_DEMO, &[]),
+    GameProfile {
+        header_texture_list_pad: true,
+        ..GameProfile::new("Spyro: A Hero's Tail", EDB_VERSION_SPYRO, &["spyro"])
+    },
+    GameProfile {
+        texture_extra_pad: true,
+        ..GameProfile::new("Robots", 248, &[])
+    },
+    GameProfile::new("Predator: Concrete Jungle", EDB_VERSION_PREDATOR, &[]),
+    GameProfile::new("Batman Begins", 251, &[]),
+    // TODO(profiles): Ice Age 2 and Pirates share a version and no layout differences are known yet. Their header flags
+    // haven't been compared, so they can only be told apart by game ID for now
+    GameProfile::new("Ice Age 2: The Meltdown", 252, &[]),
+    GameProfile::new("Pirates of the Caribbean: At World's End", 252, &["potccg"]),
+    GameProfile::new("G-Force", EDB_VERSION_GFORCE, &["gforce"]),
+    GameProfile::new("Ice Age: Dawn of the Dinosaurs", EDB_VERSION_ICEAGE3, &[]),
+    GameProfile::new("Spiderman 4", EDB_VERSION_BOND, &[]),
+    GameProfile::new("GoldenEye 007", EDB_VERSION_BOND, &[]),
+];
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Platform {
     Pc,
@@ -109,3 +214,74 @@ impl Display for Platform {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROFILES: &[GameProfile] = &[
+        GameProfile {
+            header_flags: Some((0x10, 0)),
+            ..GameProfile::new("A", 252, &["a"])
+        },
+        GameProfile {
+            header_flags: Some((0x10, 0x10)),
+            texture_extra_pad: true,
+            ..GameProfile::new("B", 252, &["b"])
+        },
+        GameProfile::new("C", 259, &[]),
+    ];
+
+    #[test]
+    fn detect_by_game_id() {
+        assert_eq!(
+            GameProfile::detect_in(PROFILES, 252, 0x10, Some("A")).name,
+            "A"
+        );
+        assert_eq!(
+            GameProfile::detect_in(PROFILES, 252, 0, Some("b")).name,
+            "B"
+        );
+
+        // The game ID has to belong to a profile with the same version
+        assert_eq!(
+            GameProfile::detect_in(PROFILES, 259, 0, Some("a")).name,
+            "C"
+        );
+    }
+
+    #[test]
+    fn detect_by_flags() {
+        assert_eq!(GameProfile::detect_in(PROFILES, 252, 0x01, None).name, "A");
+        assert_eq!(GameProfile::detect_in(PROFILES, 252, 0x11, None).name, "B");
+        assert_eq!(
+            GameProfile::detect_in(PROFILES, 252, 0x11, Some("unknown")).name,
+            "B"
+        );
+    }
+
+    #[test]
+    fn detect_fallback() {
+        assert_eq!(GameProfile::detect_in(PROFILES, 259, 0x10, None).name, "C");
+
+        let generic = GameProfile::detect_in(PROFILES, 240, 0, None);
+        assert_eq!(generic, GameProfile::generic(240));
+        assert!(!generic.texture_extra_pad);
+    }
+
+    #[test]
+    fn detect_registry() {
+        assert!(GameProfile::detect(205, 0, None).short_map_zones);
+        assert!(!GameProfile::detect(206, 0, None).short_map_zones);
+        assert!(GameProfile::detect(EDB_VERSION_SPYRO, 0, None).header_texture_list_pad);
+        assert!(GameProfile::detect(248, 0, None).texture_extra_pad);
+        assert_eq!(
+            GameProfile::detect(252, 0, Some("potccg")).name,
+            "Pirates of the Caribbean: At World's End"
+        );
+        assert_eq!(
+            GameProfile::detect(252, 0, None).name,
+            "Ice Age 2: The Meltdown"
+        );
+    }
+}
@@ -0,0 +1,248 @@
+use std::io::{Read, Seek, SeekFrom, Write};
+
+use binrw::{BinWriterExt, Endian};
+
+use crate::{edb::EdbFile, error::Result, header::EXGeoHeader, versions::GameProfile, Hashcode};
+
+/// Objects are aligned to 16 bytes when they are moved
+const OBJECT_ALIGNMENT: u32 = 0x10;
+
+/// Raw data of an object listed in the header
+#[derive(Debug, Clone)]
+pub struct EdbObject {
+    pub hashcode: Hashcode,
+    /// Absolute offset of the object in the file
+    pub address: u32,
+    pub data: Vec<u8>,
+}
+
+/// Writes an EDB file from a (modified) header and the objects listed in it
+///
+/// Eurochef doesn't parse every object in a file, so objects are kept as raw data. The size of an object is taken from
+/// the start of the next object or array listed in the header. Data that isn't part of any object (padding, data past
+/// the base file size) is copied from the original file.
+///
+/// The header, the hash arrays and their elements (including the refpointer list) are written with relative pointers
+/// recalculated from their absolute offsets. Arrays that grow need to be moved to free space with `set_data_offset_absolute` before writing.
+pub struct EdbWriter {
+    pub header: EXGeoHeader,
+    pub endian: Endian,
+    pub profile: GameProfile,
+
+    /// Objects in the order of [`EXGeoHeader::object_elements`]
+    pub objects: Vec<EdbObject>,
+
+    /// Raw file data, used for everything that isn't written from the header or objects
+    pub data: Vec<u8>,
+}
+
+impl EdbWriter {
+    /// Creates a writer from a header and the raw data of the file it was read from, splitting the data into objects
+    pub fn new(header: EXGeoHeader, endian: Endian, profile: GameProfile, data: Vec<u8>) -> Self {
+        let base_file_size = header.base_file_size.min(data.len() as u32);
+
+        let mut boundaries: Vec<u32> = header
+            .object_elements()
+            .iter()
+            .map(|e| e.address)
+            .chain(header.array_addresses())
+            .chain([base_file_size, data.len() as u32])
+            .collect();
+        boundaries.sort_unstable();
+        boundaries.dedup();
+
+        let objects = header
+            .object_elements()
+            .iter()
+            .map(|e| {
+                let start = e.address.min(data.len() as u32);
+                let end = boundaries
+                    .iter()
+                    .copied()
+                    .find(|b| *b > start)
+                    .unwrap_or(start);
+
+                EdbObject {
+                    hashcode: e.hashcode,
+                    address: e.address,
+                    data: data[start as usize..end as usize].to_vec(),
+                }
+            })
+            .collect();
+
+        Self {
+            header,
+            endian,
+            profile,
+            objects,
+            data,
+        }
+    }
+
+    /// Creates a writer from the header and raw data of a loaded file
+    pub fn from_edb(edb: &mut EdbFile) -> Result<Self> {
+        let mut data = vec![];
+        edb.seek(SeekFrom::Start(0))?;
+        edb.read_to_end(&mut data)?;
+
+        Ok(Self::new(edb.header.clone(), edb.endian, edb.profile, data))
+    }
+
+    /// Replaces the data of an object. Objects that no longer fit in their original space are moved to the end of the
+    /// base data, growing the base file size. Data past the base file size (and objects located in it) is moved back by
+    /// the same amount to make room
+    ///
+    /// Relative pointers inside the object data are not adjusted, so they should only point inside of the object itself.
+    /// The same goes for pointers into the data past the base file size
+    pub fn replace_object(&mut self, index: usize, data: Vec<u8>) {
+        if data.len() > self.objects[index].data.len() {
+            let base_file_size = self.header.base_file_size.min(self.data.len() as u32);
+            let address = align(base_file_size);
+            let new_base_file_size = align(address + data.len() as u32);
+            let shift = new_base_file_size - base_file_size;
+
+            let extra_data = self.data.split_off(base_file_size as usize);
+            self.data.resize(new_base_file_size as usize, 0);
+            self.data.extend(extra_data);
+
+            for object in &mut self.objects {
+                if object.address >= base_file_size {
+                    object.address += shift;
+                }
+            }
+
+            self.objects[index].address = address;
+            self.header.base_file_size = new_base_file_size;
+            self.header.file_size += shift;
+        }
+
+        self.objects[index].data = data;
+    }
+
+    /// Writes the file starting at the beginning of `writer`
+    pub fn write<W: Write + Seek>(&self, writer: &mut W) -> Result<()> {
+        writer.seek(SeekFrom::Start(0))?;
+        writer.write_all(&self.data)?;
+        let end = writer.stream_position()?;
+
+        for object in &self.objects {
+            writer.seek(SeekFrom::Start(object.address as u64))?;
+            writer.write_all(&object.data)?;
+        }
+
+        let mut header = self.header.clone();
+        for (element, object) in header.object_elements_mut().into_iter().zip(&self.objects) {
+            element.hashcode = object.hashcode;
+            element.address = object.address;
+        }
+
+        writer.seek(SeekFrom::Start(0))?;
+        self.write_header(&header, writer)?;
+
+        writer.seek(SeekFrom::Start(end))?;
+
+        Ok(())
+    }
+
+    fn write_header<W: Write + Seek>(&self, header: &EXGeoHeader, writer: &mut W) -> Result<()> {
+        let endian = self.endian;
+        let version = header.version;
+
+        writer.write_type_args(header, endian, (self.profile,))?;
+
+        header.section_list.write_data(writer, endian, ())?;
+        header.refpointer_list.write_data(writer, endian, ())?;
+        header.entity_list.write_data(writer, endian, (version,))?;
+        header.anim_list.write_data(writer, endian, ())?;
+        header
+            .animskin_list
+            .write_data(writer, endian, (version,))?;
+        header.animscript_list.write_data(writer, endian, ())?;
+        header.map_list.write_data(writer, endian, ())?;
+        header.animmode_list.write_data(writer, endian, ())?;
+        header.animset_list.write_data(writer, endian, ())?;
+        header.particle_list.write_data(writer, endian, ())?;
+        header.swoosh_list.write_data(writer, endian, ())?;
+        header.spreadsheet_list.write_data(writer, endian, ())?;
+        header.font_list.write_data(writer, endian, ())?;
+        if version >= 248 {
+            header.material_list.write_data(writer, endian, ())?;
+        }
+        header.texture_list.write_data(writer, endian, ())?;
+
+        Ok(())
+    }
+}
+
+fn align(offset: u32) -> u32 {
+    (offset + OBJECT_ALIGNMENT - 1) & !(OBJECT_ALIGNMENT - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{test_util::TestFile, versions::Platform};
+
+    /// Builds a little endian version 252 file with 2 maps and 1 particle, followed by 0x20 bytes of non-base data
+    fn test_file() -> Vec<u8> {
+        let mut file = TestFile::new(0x1a0, 0x180);
+
+        // The map list is the 7th array and the particle list the 10th
+        file.array(6, 2, 0x100);
+        file.element(0x100, 0x1c000000, 0, 0x140);
+        file.element(0x110, 0x1c000001, 0, 0x160);
+        file.array(9, 1, 0x120);
+        file.element(0x120, 0x11000000, 0, 0x170);
+
+        for (i, b) in file.data.iter_mut().enumerate().skip(0x140) {
+            *b = i as u8;
+        }
+
+        file.data
+    }
+
+    fn read_edb(data: Vec<u8>) -> EdbFile {
+        EdbFile::new(Box::new(Cursor::new(data)), Platform::Pc).unwrap()
+    }
+
+    #[test]
+    fn roundtrip() {
+        let data = test_file();
+        let mut edb = read_edb(data.clone());
+        let writer = EdbWriter::from_edb(&mut edb).unwrap();
+
+        let sizes: Vec<usize> = writer.objects.iter().map(|o| o.data.len()).collect();
+        assert_eq!(sizes, [0x20, 0x10, 0x10]);
+
+        let mut out = Cursor::new(vec![]);
+        writer.write(&mut out).unwrap();
+        assert_eq!(out.into_inner(), data);
+    }
+
+    #[test]
+    fn replace_object() {
+        let data = test_file();
+        let mut edb = read_edb(data.clone());
+        let mut writer = EdbWriter::from_edb(&mut edb).unwrap();
+
+        writer.replace_object(0, vec![0xaa; 0x10]);
+        writer.replace_object(1, vec![0xbb; 0x28]);
+
+        let mut out = Cursor::new(vec![]);
+        writer.write(&mut out).unwrap();
+        let out = out.into_inner();
+
+        let edb = read_edb(out.clone());
+        assert_eq!(edb.header.map_list[0].address, 0x140);
+        assert_eq!(edb.header.map_list[1].address, 0x180);
+        assert_eq!(edb.header.particle_list[0].address, 0x170);
+        assert_eq!(edb.header.base_file_size, 0x1b0);
+        assert_eq!(edb.header.file_size, 0x1d0);
+
+        assert_eq!(&out[0x140..0x150], &[0xaa; 0x10]);
+        assert_eq!(&out[0x180..0x1a8], &[0xbb; 0x28]);
+        assert_eq!(&out[0x1b0..], &data[0x180..]);
+    }
+}
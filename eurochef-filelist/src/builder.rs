@@ -0,0 +1,233 @@
+use binrw::Endian;
+
+use crate::{
+    error::{FilelistError, Result, UnsupportedError},
+    scr::{ScrFileInformation, ScrFileOptions},
+    structures::FileLoc5,
+    unified::{has_single_data_file, UXFileInfo, UXFileList},
+};
+
+/// Plans the layout of a new filelist.
+///
+/// The builder only decides where file data should go, writing the data itself is left to the caller.
+/// This allows the layout to be checked without writing any files.
+pub struct FilelistBuilder {
+    filelist: UXFileList,
+
+    /// Maximum size per data file
+    pub split_size: u64,
+    /// Default alignment of file data
+    pub alignment: u64,
+
+    /// Current size of each data file
+    data_file_sizes: Vec<u64>,
+}
+
+impl FilelistBuilder {
+    pub fn new(version: u32, endian: Endian) -> Result<Self> {
+        if !matches!(version, 4..=7 | 9..=10) {
+            return Err(FilelistError::Unsupported(UnsupportedError::Version(
+                version,
+            )));
+        }
+
+        let single_data_file = has_single_data_file(version);
+
+        Ok(Self {
+            filelist: UXFileList {
+                version,
+                num_filelists: (!single_data_file).then_some(0),
+                build_type: (!single_data_file).then_some(1),
+                endian,
+                files: vec![],
+            },
+            split_size: 0x80000000,
+            alignment: 0x800,
+            data_file_sizes: vec![0],
+        })
+    }
+
+    /// Applies the options from the `[FileInfomation]` section of an SCR file
+    pub fn apply_scr_info(&mut self, info: &ScrFileInformation) -> Result<()> {
+        if let Some(version) = info.version {
+            let mut new = Self::new(version, self.filelist.endian)?;
+            new.split_size = self.split_size;
+            new.alignment = self.alignment;
+            *self = new;
+        }
+
+        if let Some(endian) = info.endian {
+            self.filelist.endian = endian;
+        }
+
+        if self.filelist.build_type.is_some() {
+            if let Some(build_type) = info.build_type {
+                self.filelist.build_type = Some(build_type);
+            }
+        }
+
+        if let Some(split_size) = info.split_size {
+            self.split_size = split_size as u64;
+        }
+
+        if let Some(alignment) = info.alignment {
+            self.alignment = alignment as u64;
+        }
+
+        Ok(())
+    }
+
+    pub fn version(&self) -> u32 {
+        self.filelist.version
+    }
+
+    pub fn endian(&self) -> Endian {
+        self.filelist.endian
+    }
+
+    pub fn is_single_data_file(&self) -> bool {
+        self.filelist.num_filelists.is_none()
+    }
+
+    /// Adds a file to the filelist. The returned file info contains the location(s) the data should be written to
+    pub fn add_file(
+        &mut self,
+        path: &str,
+        data: &[u8],
+        options: &ScrFileOptions,
+    ) -> Result<&UXFileInfo> {
+        let default_hashcode = 0x81000000 | self.filelist.files.len() as u32;
+        let mut info = if let (Some(hashcode), Some(version), Some(flags), Some(length)) = (
+            options.hashcode,
+            options.version,
+            options.flags,
+            options.length,
+        ) {
+            UXFileInfo {
+                fileloc: vec![],
+                length,
+                hashcode,
+                version,
+                flags,
+                unk0: 0,
+                fileloc_unk0: vec![],
+            }
+        } else {
+            UXFileInfo::from_data(path, data, self.filelist.endian, default_hashcode)?
+        };
+
+        info.hashcode = options.hashcode.unwrap_or(info.hashcode);
+        info.version = options.version.unwrap_or(info.version);
+        info.flags = options.flags.unwrap_or(info.flags);
+        info.length = options.length.unwrap_or(info.length);
+
+        if !options.fileloc.is_empty() {
+            if self.is_single_data_file() && options.fileloc.iter().any(|l| l.filelist_num != 0) {
+                return Err(FilelistError::Unsupported(
+                    UnsupportedError::MultipleLocations(self.filelist.version),
+                ));
+            }
+
+            for loc in &options.fileloc {
+                let num = loc.filelist_num as usize;
+                if self.data_file_sizes.len() <= num {
+                    self.data_file_sizes.resize(num + 1, 0);
+                }
+
+                let end = loc.addr as u64 + data.len() as u64;
+                self.data_file_sizes[num] = self.data_file_sizes[num].max(end);
+            }
+
+            info.fileloc = options.fileloc.clone();
+        } else {
+            let alignment = options
+                .alignment
+                .map(|a| a as u64)
+                .unwrap_or(self.alignment)
+                .max(1);
+
+            let mut filelist_num = self.data_file_sizes.len() - 1;
+            let mut addr = align(self.data_file_sizes[filelist_num], alignment);
+
+            if !self.is_single_data_file()
+                && (options.split || addr + data.len() as u64 > self.split_size)
+                && self.data_file_sizes[filelist_num] != 0
+            {
+                self.data_file_sizes.push(0);
+                filelist_num += 1;
+                addr = 0;
+            }
+
+            self.data_file_sizes[filelist_num] = addr + data.len() as u64;
+            info.fileloc = vec![FileLoc5 {
+                addr: addr as u32,
+                filelist_num: filelist_num as u32,
+            }];
+        }
+
+        self.filelist.files.push((path.to_string(), info));
+        Ok(&self.filelist.files.last().unwrap().1)
+    }
+
+    /// Sizes of the data files, excluding any trailing padding
+    pub fn data_file_sizes(&self) -> &[u64] {
+        &self.data_file_sizes
+    }
+
+    /// Sizes of the data files once padded to at least `min_sizes`, eg. the data file sizes recorded in an SCR file
+    pub fn padded_data_file_sizes(&self, min_sizes: &[u64]) -> Vec<u64> {
+        self.data_file_sizes
+            .iter()
+            .enumerate()
+            .map(|(i, size)| (*size).max(min_sizes.get(i).copied().unwrap_or(0)))
+            .collect()
+    }
+
+    pub fn finish(mut self) -> UXFileList {
+        if !self.is_single_data_file() {
+            self.filelist.num_filelists = Some((self.data_file_sizes.len() - 1) as u16);
+        }
+
+        self.filelist
+    }
+}
+
+fn align(offset: u64, alignment: u64) -> u64 {
+    (offset + alignment - 1) / alignment * alignment
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verify::diff_data_file_sizes;
+
+    #[test]
+    fn padded_data_file_sizes() {
+        let mut builder = FilelistBuilder::new(7, Endian::Little).unwrap();
+        builder.split_size = 0x1000;
+        for i in 0..3 {
+            builder
+                .add_file(
+                    &format!("x:\\game\\file{i}.txt"),
+                    &[0u8; 0x900],
+                    &ScrFileOptions::default(),
+                )
+                .unwrap();
+        }
+        assert_eq!(builder.data_file_sizes(), [0x900, 0x900, 0x900]);
+
+        // Data files on disk are padded to the sizes recorded in the SCR file, or to the data alignment after patching
+        let on_disk = [0x1000, 0x1000, 0x1000];
+        assert_eq!(
+            builder.padded_data_file_sizes(&[0x1000, 0x1000]),
+            [0x1000, 0x1000, 0x900]
+        );
+        assert!(
+            diff_data_file_sizes(&on_disk, &builder.padded_data_file_sizes(&on_disk)).is_empty()
+        );
+        assert_eq!(
+            builder.padded_data_file_sizes(&[0x100]),
+            builder.data_file_sizes()
+        );
+    }
+}
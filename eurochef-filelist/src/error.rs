@@ -0,0 +1,45 @@
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, FilelistError>;
+
+#[derive(Error, Debug)]
+pub enum FilelistError {
+    #[error("Unsupported: {0}")]
+    Unsupported(UnsupportedError),
+
+    #[error("The filelist header is truncated")]
+    TruncatedHeader,
+
+    #[error("Filename offset 0x{offset:x} of file {index} points outside of the filelist header")]
+    BadFilenameOffset { index: usize, offset: u64 },
+
+    #[error("Filename {0} is not null-terminated")]
+    UnterminatedFilename(u32),
+
+    #[error("Missing data file {0}")]
+    MissingDataFile(String),
+
+    #[error("SCR parse error on line {line}: {message}")]
+    ScrParse { line: usize, message: String },
+
+    #[error("File {0} not found in filelist")]
+    FileNotFound(String),
+
+    #[error("Input/output error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("BinRW error")]
+    BinRw(#[from] binrw::Error),
+}
+
+#[derive(Error, Debug)]
+pub enum UnsupportedError {
+    #[error("Unsupported filelist version {0}")]
+    Version(u32),
+
+    #[error("Can't convert a version {0} filelist to version {1}")]
+    Conversion(u32, &'static str),
+
+    #[error("Version {0} filelists only support a single location per file")]
+    MultipleLocations(u32),
+}
@@ -0,0 +1,143 @@
+use crate::{error::Result, scr::glob_match, unified::UXFileInfo, vfs::FilelistVfs};
+
+/// Selects files from a filelist by path, hashcode or file type
+///
+/// Empty include and hashcode lists match every file. Patterns use the SCR wildcard semantics, see [`glob_match`]
+#[derive(Debug, Clone, Default)]
+pub struct FileFilter {
+    /// Patterns of which at least one has to match
+    pub include: Vec<String>,
+    /// Patterns of which none may match
+    pub exclude: Vec<String>,
+    /// Hashcodes of which one has to match
+    pub hashcodes: Vec<u32>,
+    /// Only match geo files (files starting with the GEOM magic)
+    pub only_geo: bool,
+}
+
+impl FileFilter {
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty()
+            && self.exclude.is_empty()
+            && self.hashcodes.is_empty()
+            && !self.only_geo
+    }
+
+    /// Checks the path and hashcode filters. Doesn't check `only_geo`, as that requires reading the file data
+    pub fn matches_entry(&self, path: &str, info: &UXFileInfo) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|p| glob_match(p, path)))
+            && !self.exclude.iter().any(|p| glob_match(p, path))
+            && (self.hashcodes.is_empty() || self.hashcodes.contains(&info.hashcode))
+    }
+
+    /// Checks every filter against the file at the given index
+    pub fn matches(&self, vfs: &mut FilelistVfs, index: usize) -> Result<bool> {
+        let (path, info) = &vfs.filelist().files[index];
+        if !self.matches_entry(path, info) {
+            return Ok(false);
+        }
+
+        if self.only_geo {
+            return vfs.is_geo(index);
+        }
+
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(hashcode: u32) -> UXFileInfo {
+        UXFileInfo {
+            fileloc: vec![],
+            length: 0,
+            hashcode,
+            version: 0,
+            flags: 0,
+            unk0: 0,
+            fileloc_unk0: vec![],
+        }
+    }
+
+    fn filter(include: &[&str], exclude: &[&str], hashcodes: &[u32]) -> FileFilter {
+        FileFilter {
+            include: include.iter().map(|s| s.to_string()).collect(),
+            exclude: exclude.iter().map(|s| s.to_string()).collect(),
+            hashcodes: hashcodes.to_vec(),
+            only_geo: false,
+        }
+    }
+
+    const EDB: &str = "x:\\gforce\\binary\\_bin_pc\\mw_intobj.edb";
+    const SFX: &str = "x:\\gforce\\sound\\_bin_pc\\music.sfx";
+
+    #[test]
+    fn empty_filter() {
+        let f = FileFilter::default();
+        assert!(f.is_empty());
+        assert!(f.matches_entry(EDB, &info(0x81000001)));
+        assert!(!filter(&[], &[], &[1]).is_empty());
+    }
+
+    #[test]
+    fn include_and_exclude() {
+        let f = filter(&["x:\\gforce\\binary\\_bin_pc\\*.edb"], &[], &[]);
+        assert!(f.matches_entry(EDB, &info(0)));
+        assert!(!f.matches_entry(SFX, &info(0)));
+
+        // Any include pattern may match
+        let f = filter(&["*\\*\\*\\*.edb", "*\\*\\*\\*.sfx"], &[], &[]);
+        assert!(f.matches_entry(EDB, &info(0)));
+        assert!(f.matches_entry(SFX, &info(0)));
+
+        // Excludes win over includes
+        let f = filter(&["*\\*\\*\\*.edb"], &["*\\*\\*\\mw_*.edb"], &[]);
+        assert!(!f.matches_entry(EDB, &info(0)));
+
+        // Excludes alone match everything else
+        let f = filter(&[], &["*\\*\\*\\*.sfx"], &[]);
+        assert!(f.matches_entry(EDB, &info(0)));
+        assert!(!f.matches_entry(SFX, &info(0)));
+    }
+
+    #[test]
+    fn hashcodes() {
+        let f = filter(&[], &[], &[0x81000001, 0x81000002]);
+        assert!(f.matches_entry(EDB, &info(0x81000002)));
+        assert!(!f.matches_entry(EDB, &info(0x81000003)));
+
+        // Paths and hashcodes both have to match
+        let f = filter(&["*\\*\\*\\*.edb"], &[], &[0x81000001]);
+        assert!(f.matches_entry(EDB, &info(0x81000001)));
+        assert!(!f.matches_entry(SFX, &info(0x81000001)));
+        assert!(!f.matches_entry(EDB, &info(0x81000002)));
+
+        let f = filter(&[], &["*\\*\\*\\*.edb"], &[0x81000001]);
+        assert!(!f.matches_entry(EDB, &info(0x81000001)));
+    }
+
+    #[test]
+    fn path_handling() {
+        // Drive letters are optional, separators and casing don't matter
+        for pattern in [
+            "x:\\gforce\\binary\\_bin_pc\\mw_intobj.edb",
+            "c:\\gforce\\binary\\_bin_pc\\mw_intobj.edb",
+            "gforce/binary/_bin_pc/MW_INTOBJ.EDB",
+            "/GForce/Binary/*/mw_intobj.edb",
+        ] {
+            assert!(
+                filter(&[pattern], &[], &[]).matches_entry(EDB, &info(0)),
+                "{pattern}"
+            );
+            assert!(
+                !filter(&[], &[pattern], &[]).matches_entry(EDB, &info(0)),
+                "{pattern}"
+            );
+        }
+
+        // Wildcards don't cross directories
+        assert!(!filter(&["x:\\gforce\\*.edb"], &[], &[]).matches_entry(EDB, &info(0)));
+    }
+}
@@ -1,11 +1,21 @@
+pub mod builder;
+pub mod error;
+pub mod filter;
+pub mod patch;
 pub mod path;
+pub mod scr;
 pub mod structures;
 pub mod unified;
 pub mod v4;
 pub mod v5;
 pub mod v9;
+pub mod verify;
+pub mod vfs;
 
-pub use unified::UXFileList;
+pub use error::FilelistError;
+pub use filter::FileFilter;
+pub use unified::{EXFileList, UXFileList};
 pub use v4::EXFileList4;
 pub use v5::EXFileList5;
 pub use v9::EXFileList9;
+pub use vfs::FilelistVfs;
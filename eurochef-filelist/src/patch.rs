@@ -0,0 +1,342 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufReader, BufWriter, Seek, SeekFrom, Write},
+    path::Path,
+};
+
+use crate::{
+    error::{FilelistError, Result},
+    structures::FileLoc5,
+    unified::{has_single_data_file, UXFileInfo, UXFileList},
+    vfs::{data_file_path, normalize_path},
+};
+
+/// Data files are aligned to 2048 bytes (the sector size of most disc-based platforms)
+const DATA_ALIGNMENT: u64 = 0x800;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PatchTarget {
+    /// Replaces the file with the given path, or adds a new file if it doesn't exist yet
+    Path(String),
+    /// Replaces the file with the given hashcode
+    Hashcode(u32),
+}
+
+#[derive(Debug, Clone)]
+pub struct FilePatch {
+    pub target: PatchTarget,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PatchOutcome {
+    /// An existing file was replaced. `previous_copies` is the number of locations the file was stored at before
+    Replaced {
+        path: String,
+        previous_copies: usize,
+    },
+    Added(String),
+}
+
+/// Replaces or adds files in an existing filelist without repacking it.
+///
+/// Every target is resolved before anything is written, so a missing target leaves the filelist untouched.
+/// New data is appended to the last data file, or to a new data file when it would exceed `split_size`.
+/// The header is rewritten afterwards through a temporary file, so the original header is left untouched if anything fails.
+/// Previous copies of replaced files are left in the data files, but are no longer referenced.
+/// Replaced files are stored once, even if the original was stored at multiple locations.
+///
+/// Paths of added files are normalized to the drive-prefixed form used by the filelist (eg. `x:\game\binary\file.edb`)
+pub fn patch_filelist<P: AsRef<Path>>(
+    path: P,
+    patches: &[FilePatch],
+    split_size: u32,
+) -> Result<Vec<PatchOutcome>> {
+    let path = path.as_ref();
+    let mut filelist = UXFileList::read(&mut BufReader::new(File::open(path)?))?;
+
+    let single_data_file = has_single_data_file(filelist.version);
+    let mut filelist_num = filelist.num_filelists.unwrap_or(0) as u32;
+    let mut data_file = open_for_append(path, (!single_data_file).then_some(filelist_num))?;
+    let drive_letter = filelist
+        .files
+        .first()
+        .and_then(|(f, _)| f.chars().next())
+        .filter(|c| c.is_ascii_alphabetic())
+        .unwrap_or('x');
+
+    // Resolve every target first, added files get an entry without any locations
+    let mut resolved = vec![];
+    let mut outcomes = vec![];
+    for patch in patches {
+        let index = match &patch.target {
+            PatchTarget::Path(p) => {
+                let normalized = normalize_path(p);
+                filelist
+                    .files
+                    .iter()
+                    .position(|(f, _)| normalize_path(f) == normalized)
+            }
+            PatchTarget::Hashcode(hc) => filelist.files.iter().position(|(_, i)| i.hashcode == *hc),
+        };
+
+        match (index, &patch.target) {
+            (Some(i), _) => {
+                let (vpath, original) = &filelist.files[i];
+                let mut info =
+                    UXFileInfo::from_data(vpath, &patch.data, filelist.endian, original.hashcode)?;
+
+                // Keep the unknown fields of the entry being replaced
+                info.unk0 = original.unk0;
+                info.fileloc_unk0 = original.fileloc_unk0.iter().take(1).copied().collect();
+
+                outcomes.push(PatchOutcome::Replaced {
+                    path: vpath.clone(),
+                    previous_copies: original.fileloc.len(),
+                });
+                filelist.files[i].1 = info;
+                resolved.push(i);
+            }
+            (None, PatchTarget::Path(p)) => {
+                let vpath = format!("{drive_letter}:\\{}", normalize_path(p));
+                let info = UXFileInfo::from_data(
+                    &vpath,
+                    &patch.data,
+                    filelist.endian,
+                    0x81000000 | filelist.files.len() as u32,
+                )?;
+
+                outcomes.push(PatchOutcome::Added(vpath.clone()));
+                filelist.files.push((vpath, info));
+                resolved.push(filelist.files.len() - 1);
+            }
+            (None, PatchTarget::Hashcode(hc)) => {
+                return Err(FilelistError::FileNotFound(format!("{hc:x}")));
+            }
+        }
+    }
+
+    for (patch, index) in patches.iter().zip(resolved) {
+        let mut addr = align(data_file.seek(SeekFrom::End(0))?);
+        if !single_data_file && addr + patch.data.len() as u64 > split_size as u64 {
+            filelist_num += 1;
+            data_file = File::create(data_file_path(path, Some(filelist_num)))?;
+            addr = 0;
+        }
+
+        data_file.seek(SeekFrom::Start(addr))?;
+        data_file.write_all(&patch.data)?;
+
+        filelist.files[index].1.fileloc = vec![FileLoc5 {
+            addr: addr as u32,
+            filelist_num: if single_data_file { 0 } else { filelist_num },
+        }];
+    }
+
+    // Pad the last data file so that the next file can be appended directly
+    let end = data_file.seek(SeekFrom::End(0))?;
+    let aligned_end = align(end);
+    if aligned_end != end {
+        data_file.write_all(&vec![0u8; (aligned_end - end) as usize])?;
+    }
+
+    if !single_data_file {
+        filelist.num_filelists = Some(filelist_num as u16);
+    }
+
+    let temp_path = path.with_extension("bin.tmp");
+    {
+        let mut writer = BufWriter::new(File::create(&temp_path)?);
+        filelist.write(&mut writer)?;
+        writer.flush()?;
+    }
+    std::fs::rename(&temp_path, path)?;
+
+    Ok(outcomes)
+}
+
+fn open_for_append(path: &Path, filelist_num: Option<u32>) -> Result<File> {
+    let data_path = data_file_path(path, filelist_num);
+    OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(&data_path)
+        .map_err(|e| {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                FilelistError::MissingDataFile(data_path.to_string_lossy().to_string())
+            } else {
+                e.into()
+            }
+        })
+}
+
+fn align(offset: u64) -> u64 {
+    (offset + DATA_ALIGNMENT - 1) & !(DATA_ALIGNMENT - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use binrw::Endian;
+
+    use super::*;
+    use crate::{
+        structures::{EXFileListHeader9, FileInfo9, FileLoc9},
+        EXFileList, EXFileList9, FilelistVfs,
+    };
+
+    /// Writes a version 10 filelist with 3 files to a fresh temporary directory. The last file is stored twice
+    fn write_test_filelist(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("eurochef-patch-{}-{name}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("Filelist.bin");
+
+        let mut data = vec![];
+        let mut fileinfo = vec![];
+        for i in 0..3u32 {
+            let addr = align(data.len() as u64);
+            data.resize(addr as usize, 0);
+            data.extend(std::iter::repeat(i as u8).take(0x10));
+            let copies = if i == 2 { 2 } else { 1 };
+            fileinfo.push(FileInfo9 {
+                unk0: 0xdead0000 | i,
+                hashcode: 0x81000000 | i,
+                version: 0,
+                flags: 0,
+                length: 0x10,
+                fileloc: (0..copies)
+                    .map(|copy| FileLoc9 {
+                        unk0: 0xbeef0000 | (copy << 8) | i,
+                        addr: addr as u32,
+                        filelist_num: 0,
+                    })
+                    .collect(),
+            });
+        }
+        std::fs::write(data_file_path(&path, Some(0)), data).unwrap();
+
+        let filelist = EXFileList9 {
+            endian: Endian::Little,
+            header: EXFileListHeader9 {
+                version: 10,
+                filesize: 0,
+                build_type: 1,
+                num_filelists: 0,
+                filename_list_offset: 0,
+                fileinfo,
+            },
+            filenames: (0..3)
+                .map(|i| format!("x:\\game\\data\\file{i}.txt"))
+                .collect(),
+        };
+        filelist.write(&mut File::create(&path).unwrap()).unwrap();
+
+        path
+    }
+
+    #[test]
+    fn patch_v10() {
+        let path = write_test_filelist("v10");
+
+        let outcomes = patch_filelist(
+            &path,
+            &[
+                FilePatch {
+                    target: PatchTarget::Path("game/DATA/file1.txt".to_string()),
+                    data: vec![0xaa; 0x20],
+                },
+                FilePatch {
+                    target: PatchTarget::Path("Game/Data/new.txt".to_string()),
+                    data: vec![0xbb; 0x8],
+                },
+                FilePatch {
+                    target: PatchTarget::Hashcode(0x81000002),
+                    data: vec![0xcc; 0x10],
+                },
+            ],
+            0x80000000,
+        )
+        .unwrap();
+
+        assert_eq!(
+            outcomes,
+            [
+                PatchOutcome::Replaced {
+                    path: "x:\\game\\data\\file1.txt".to_string(),
+                    previous_copies: 1,
+                },
+                PatchOutcome::Added("x:\\game\\data\\new.txt".to_string()),
+                PatchOutcome::Replaced {
+                    path: "x:\\game\\data\\file2.txt".to_string(),
+                    previous_copies: 2,
+                },
+            ]
+        );
+
+        let mut vfs = FilelistVfs::open_filelist(&path).unwrap();
+        let files = &vfs.filelist().files;
+        assert_eq!(files.len(), 4);
+
+        // Unknown fields of untouched and replaced entries are kept
+        assert_eq!(files[0].1.unk0, 0xdead0000);
+        assert_eq!(files[0].1.fileloc_unk0, [0xbeef0000]);
+        assert_eq!(files[1].1.unk0, 0xdead0001);
+        assert_eq!(files[1].1.fileloc_unk0, [0xbeef0001]);
+        assert_eq!(files[1].1.hashcode, 0x81000001);
+
+        // Replaced files are stored once
+        assert_eq!(files[2].1.fileloc.len(), 1);
+        assert_eq!(files[2].1.fileloc_unk0, [0xbeef0002]);
+
+        assert_eq!(
+            vfs.open("x:\\game\\data\\file0.txt").unwrap().into_inner(),
+            [0u8; 0x10]
+        );
+        assert_eq!(
+            vfs.open("x:\\game\\data\\file1.txt").unwrap().into_inner(),
+            [0xaa; 0x20]
+        );
+        assert_eq!(
+            vfs.open("x:\\game\\data\\new.txt").unwrap().into_inner(),
+            [0xbb; 0x8]
+        );
+        assert_eq!(
+            vfs.open("x:\\game\\data\\file2.txt").unwrap().into_inner(),
+            [0xcc; 0x10]
+        );
+
+        assert!(!path.with_extension("bin.tmp").exists());
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn missing_target_writes_nothing() {
+        let path = write_test_filelist("missing");
+        let data_path = data_file_path(&path, Some(0));
+        let original = std::fs::read(&path).unwrap();
+        let original_data = std::fs::read(&data_path).unwrap();
+
+        // The valid target before the missing one must not be written either
+        let result = patch_filelist(
+            &path,
+            &[
+                FilePatch {
+                    target: PatchTarget::Path("game/data/file0.txt".to_string()),
+                    data: vec![0xaa; 0x20],
+                },
+                FilePatch {
+                    target: PatchTarget::Hashcode(0x81001234),
+                    data: vec![0; 4],
+                },
+            ],
+            0x80000000,
+        );
+
+        assert!(matches!(result, Err(FilelistError::FileNotFound(_))));
+        assert_eq!(std::fs::read(&path).unwrap(), original);
+        assert_eq!(std::fs::read(&data_path).unwrap(), original_data);
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
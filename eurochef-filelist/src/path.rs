@@ -1,4 +1,8 @@
-pub fn unscramble_filename_v7(file_index: u32, bytes: &mut [u8]) {
+use crate::error::{FilelistError, Result};
+
+/// Unscrambles a filename in-place, stopping at the null terminator.
+/// Returns an error if no null terminator was found
+pub fn unscramble_filename_v7(file_index: u32, bytes: &mut [u8]) -> Result<()> {
     for i in 0..bytes.len() {
         bytes[i] = (bytes[i] as u32)
             .wrapping_add(0x16)
@@ -6,22 +10,34 @@ pub fn unscramble_filename_v7(file_index: u32, bytes: &mut [u8]) {
             .wrapping_sub(i as u32) as u8;
 
         if bytes[i] == 0 {
-            break;
+            return Ok(());
         }
     }
+
+    Err(FilelistError::UnterminatedFilename(file_index))
 }
 
+/// Scrambles a filename in-place, including the null terminator.
+/// Returns an error if the filename is not null-terminated
 // TODO: This should take a string and output a Cow<[u8]> to ensure null-termination
-pub fn scramble_filename_v7(file_index: u32, bytes: &mut [u8]) {
+pub fn scramble_filename_v7(file_index: u32, bytes: &mut [u8]) -> Result<()> {
+    if bytes.last() != Some(&0) {
+        return Err(FilelistError::UnterminatedFilename(file_index));
+    }
+
     for i in 0..bytes.len() {
         bytes[i] = (bytes[i] as u32)
             .wrapping_sub(0x16)
             .wrapping_add(file_index)
             .wrapping_add(i as u32) as u8;
     }
+
+    Ok(())
 }
 
-pub fn unscramble_filename_v10(file_index: u32, bytes: &mut [u8]) {
+/// Unscrambles a filename in-place, stopping at the null terminator.
+/// Returns an error if no null terminator was found
+pub fn unscramble_filename_v10(file_index: u32, bytes: &mut [u8]) -> Result<()> {
     for i in 0..bytes.len() {
         bytes[i] = (bytes[i] as u32)
             .wrapping_sub(0x6a)
@@ -29,9 +45,28 @@ pub fn unscramble_filename_v10(file_index: u32, bytes: &mut [u8]) {
             .wrapping_sub(i as u32 * 4) as u8;
 
         if bytes[i] == 0 {
-            break;
+            return Ok(());
         }
     }
+
+    Err(FilelistError::UnterminatedFilename(file_index))
+}
+
+/// Scrambles a filename in-place, including the null terminator.
+/// Returns an error if the filename is not null-terminated
+pub fn scramble_filename_v10(file_index: u32, bytes: &mut [u8]) -> Result<()> {
+    if bytes.last() != Some(&0) {
+        return Err(FilelistError::UnterminatedFilename(file_index));
+    }
+
+    for (i, b) in bytes.iter_mut().enumerate() {
+        *b = (*b as u32)
+            .wrapping_add(0x6a)
+            .wrapping_add(file_index * 4)
+            .wrapping_add(i as u32 * 4) as u8;
+    }
+
+    Ok(())
 }
 
 #[cfg(test)]
@@ -46,7 +81,7 @@ mod tests {
             0xD0, 0xD6, 0xDD, 0xD9, 0xCD, 0xD6, 0x9B, 0xD3, 0xD3, 0xD2, 0x71,
         ];
 
-        unscramble_filename_v7(353, &mut input);
+        unscramble_filename_v7(353, &mut input).unwrap();
 
         assert_eq!(&input, b"x:\\gforce\\binary\\_bin_pc\\mw_intobj.edb\0")
     }
@@ -60,8 +95,35 @@ mod tests {
             0x6C, 0x77, 0x74, 0x6A, 0x83, 0x39, 0x71, 0x71, 0x70, 0x0F,
         ];
 
-        scramble_filename_v7(0, &mut input);
+        scramble_filename_v7(0, &mut input).unwrap();
 
         assert_eq!(&input, &output)
     }
+
+    #[test]
+    fn scramble_path_v10() {
+        let mut input = b"x:\\goldeneye\\binary\\_bin_wii\\dam.edb\0".to_vec();
+        let output: [u8; 37] = [
+            0xB2, 0x78, 0x9E, 0xAD, 0xB9, 0xBA, 0xB6, 0xBB, 0xC8, 0xC3, 0xDB, 0xCB, 0xC6, 0xD0,
+            0xDB, 0xE4, 0xDB, 0xF0, 0xFB, 0xE2, 0xE9, 0xF0, 0xFB, 0x04, 0xF9, 0x15, 0x0B, 0x0F,
+            0x06, 0x12, 0x13, 0x23, 0xE8, 0x23, 0x26, 0x28, 0xCA,
+        ];
+
+        scramble_filename_v10(52, &mut input).unwrap();
+
+        assert_eq!(&input, &output)
+    }
+
+    #[test]
+    fn unscramble_path_v10() {
+        let mut input: [u8; 37] = [
+            0xB2, 0x78, 0x9E, 0xAD, 0xB9, 0xBA, 0xB6, 0xBB, 0xC8, 0xC3, 0xDB, 0xCB, 0xC6, 0xD0,
+            0xDB, 0xE4, 0xDB, 0xF0, 0xFB, 0xE2, 0xE9, 0xF0, 0xFB, 0x04, 0xF9, 0x15, 0x0B, 0x0F,
+            0x06, 0x12, 0x13, 0x23, 0xE8, 0x23, 0x26, 0x28, 0xCA,
+        ];
+
+        unscramble_filename_v10(52, &mut input).unwrap();
+
+        assert_eq!(&input, b"x:\\goldeneye\\binary\\_bin_wii\\dam.edb\0")
+    }
 }
@@ -0,0 +1,571 @@
+//! Parser and writer for Eurocom filelist scripts (.scr)
+//!
+//! An SCR file consists of ini-like sections:
+//! * `[FileInfomation]` (sic) contains `Key=Value` options for the whole filelist
+//! * `[FileList]` contains one path or wildcard pattern per line, optionally followed by per-file options
+//!
+//! Any other section is preserved as-is. Lines starting with `;` or `//` are comments, lines before the first section are ignored.
+//!
+//! Eurocom's SCR files only list one path per line. The per-file options below are an eurochef extension, used to
+//! record the exact layout of an extracted filelist so that it can be rebuilt identically.
+//! Options are separated by whitespace and follow the path. Paths may contain whitespace, only a trailing run of known
+//! options is parsed as options. Paths can be quoted to avoid any ambiguity:
+//! ```text
+//! x:\gforce\binary\_bin_pc\*.edb align=0x800
+//! x:\gforce\binary\_bin_pc\mw_intobj.edb split hashcode=0x80000001 version=259 flags=0x0
+//! "x:\gforce\some file.txt" loc=1:0x5800 loc=2:0x0
+//! ```
+
+use std::{
+    collections::HashMap,
+    fmt::Write as _,
+    io::{Read, Write},
+};
+
+use binrw::Endian;
+
+use crate::{
+    error::{FilelistError, Result},
+    structures::FileLoc5,
+    unified::UXFileList,
+    vfs::normalize_path,
+};
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScrFile {
+    pub info: ScrFileInformation,
+    pub entries: Vec<ScrEntry>,
+
+    /// Sections that are not interpreted by eurochef, as (name, lines)
+    pub other_sections: Vec<(String, Vec<String>)>,
+}
+
+/// Options from the `[FileInfomation]` section
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScrFileInformation {
+    pub version: Option<u32>,
+    pub build_type: Option<u16>,
+    pub endian: Option<Endian>,
+    /// Maximum size per data file
+    pub split_size: Option<u32>,
+    /// Default alignment of files in the data files
+    pub alignment: Option<u32>,
+    /// Sizes of the data files, including any trailing padding
+    pub data_file_sizes: Vec<u64>,
+
+    /// Options that are not interpreted by eurochef, as (key, value)
+    pub other: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScrEntry {
+    /// Path or wildcard pattern of the file(s) in the filelist
+    pub pattern: String,
+    pub options: ScrFileOptions,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScrFileOptions {
+    /// `align=<n>`: alignment of the file data
+    pub alignment: Option<u32>,
+    /// `split`: start a new data file before this file
+    pub split: bool,
+    /// `hashcode=<n>`
+    pub hashcode: Option<u32>,
+    /// `version=<n>`
+    pub version: Option<u32>,
+    /// `flags=<n>`
+    pub flags: Option<u32>,
+    /// `length=<n>`: the length stored in the filelist, which is not necessarily the size of the file
+    pub length: Option<u32>,
+    /// `loc=<data file>:<address>`: fixed location(s) of the file data. Can be specified multiple times to store duplicate copies
+    pub fileloc: Vec<FileLoc5>,
+}
+
+impl ScrEntry {
+    pub fn is_wildcard(&self) -> bool {
+        self.pattern.contains(['*', '?', '#'])
+    }
+}
+
+impl ScrFile {
+    pub fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let mut data = String::new();
+        reader.read_to_string(&mut data)?;
+        Self::parse(&data)
+    }
+
+    pub fn parse(data: &str) -> Result<Self> {
+        let mut scr = ScrFile::default();
+
+        enum Section {
+            None,
+            FileInformation,
+            FileList,
+            Other,
+        }
+
+        let mut section = Section::None;
+        for (line_index, l) in data.lines().enumerate() {
+            let line = l.trim();
+            let error = |message: String| FilelistError::ScrParse {
+                line: line_index + 1,
+                message,
+            };
+
+            if line.is_empty() || line.starts_with(';') || line.starts_with("//") {
+                continue;
+            }
+
+            if line.starts_with('[') && line.ends_with(']') {
+                let name = &line[1..line.len() - 1];
+                section = if name.eq_ignore_ascii_case("FileInfomation") {
+                    Section::FileInformation
+                } else if name.eq_ignore_ascii_case("FileList") {
+                    Section::FileList
+                } else {
+                    scr.other_sections.push((name.to_string(), vec![]));
+                    Section::Other
+                };
+                continue;
+            }
+
+            match section {
+                // Text before the first section is ignored
+                Section::None => {}
+                Section::FileInformation => {
+                    let (key, value) = line
+                        .split_once('=')
+                        .ok_or_else(|| error(format!("Expected Key=Value, got '{line}'")))?;
+                    let (key, value) = (key.trim(), value.trim());
+
+                    let info = &mut scr.info;
+                    match key.to_ascii_lowercase().as_str() {
+                        "version" => info.version = Some(parse_number(value).map_err(error)?),
+                        "buildtype" => info.build_type = Some(parse_number(value).map_err(error)?),
+                        "endian" => {
+                            info.endian = Some(match value.to_ascii_lowercase().as_str() {
+                                "little" => Endian::Little,
+                                "big" => Endian::Big,
+                                _ => return Err(error(format!("Invalid endian '{value}'"))),
+                            })
+                        }
+                        "splitsize" => info.split_size = Some(parse_number(value).map_err(error)?),
+                        "alignment" => info.alignment = Some(parse_number(value).map_err(error)?),
+                        "datafilesizes" => {
+                            for size in value.split(',') {
+                                info.data_file_sizes
+                                    .push(parse_number::<u64>(size.trim()).map_err(error)?);
+                            }
+                        }
+                        _ => info.other.push((key.to_string(), value.to_string())),
+                    }
+                }
+                Section::FileList => {
+                    scr.entries.push(parse_entry(line).map_err(error)?);
+                }
+                Section::Other => {
+                    // Guaranteed to exist, the section is pushed when its header is parsed
+                    scr.other_sections
+                        .last_mut()
+                        .unwrap()
+                        .1
+                        .push(line.to_string());
+                }
+            }
+        }
+
+        Ok(scr)
+    }
+
+    pub fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        writer.write_all(self.to_string().as_bytes())?;
+        Ok(())
+    }
+
+    /// Creates an SCR file that describes the exact layout of a filelist
+    pub fn from_filelist(filelist: &UXFileList, data_file_sizes: Vec<u64>) -> Self {
+        ScrFile {
+            info: ScrFileInformation {
+                version: Some(filelist.version),
+                build_type: filelist.build_type,
+                endian: Some(filelist.endian),
+                split_size: None,
+                alignment: None,
+                data_file_sizes,
+                other: vec![],
+            },
+            entries: filelist
+                .files
+                .iter()
+                .map(|(path, info)| ScrEntry {
+                    pattern: path.clone(),
+                    options: ScrFileOptions {
+                        alignment: None,
+                        split: false,
+                        hashcode: Some(info.hashcode),
+                        version: Some(info.version),
+                        flags: Some(info.flags),
+                        length: Some(info.length),
+                        fileloc: info.fileloc.clone(),
+                    },
+                })
+                .collect(),
+            other_sections: vec![],
+        }
+    }
+
+    /// Matches the entries against the given filelist paths, in SCR order.
+    ///
+    /// Entries without wildcards are returned as-is, even when they are not in `paths`.
+    /// Paths matching multiple entries are only returned for the first entry.
+    pub fn resolve<'a>(&'a self, paths: &[String]) -> Vec<(&'a ScrEntry, String)> {
+        let mut result: Vec<(&ScrEntry, String)> = vec![];
+        let mut used = vec![false; paths.len()];
+        let path_index: HashMap<String, usize> = paths
+            .iter()
+            .enumerate()
+            .map(|(i, p)| (normalize_path(p), i))
+            .collect();
+
+        for entry in &self.entries {
+            if !entry.is_wildcard() {
+                if let Some(&i) = path_index.get(&normalize_path(&entry.pattern)) {
+                    if used[i] {
+                        continue;
+                    }
+
+                    used[i] = true;
+                }
+
+                result.push((entry, entry.pattern.clone()));
+                continue;
+            }
+
+            for (i, p) in paths.iter().enumerate() {
+                if !used[i] && glob_match(&entry.pattern, p) {
+                    used[i] = true;
+                    result.push((entry, p.clone()));
+                }
+            }
+        }
+
+        result
+    }
+}
+
+impl std::fmt::Display for ScrFile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let info = &self.info;
+        writeln!(f, "[FileInfomation]")?;
+        if let Some(v) = info.version {
+            writeln!(f, "Version={v}")?;
+        }
+        if let Some(v) = info.build_type {
+            writeln!(f, "BuildType={v}")?;
+        }
+        if let Some(v) = info.endian {
+            let endian = match v {
+                Endian::Big => "Big",
+                Endian::Little => "Little",
+            };
+            writeln!(f, "Endian={endian}")?;
+        }
+        if let Some(v) = info.split_size {
+            writeln!(f, "SplitSize=0x{v:x}")?;
+        }
+        if let Some(v) = info.alignment {
+            writeln!(f, "Alignment=0x{v:x}")?;
+        }
+        if !info.data_file_sizes.is_empty() {
+            let sizes: Vec<String> = info
+                .data_file_sizes
+                .iter()
+                .map(|s| format!("0x{s:x}"))
+                .collect();
+            writeln!(f, "DataFileSizes={}", sizes.join(","))?;
+        }
+        for (k, v) in &info.other {
+            writeln!(f, "{k}={v}")?;
+        }
+
+        writeln!(f, "\n[FileList]")?;
+        for e in &self.entries {
+            let mut line = String::new();
+            if e.pattern.is_empty() || e.pattern.contains(char::is_whitespace) {
+                write!(line, "\"{}\"", e.pattern)?;
+            } else {
+                line.push_str(&e.pattern);
+            }
+
+            let o = &e.options;
+            if let Some(v) = o.alignment {
+                write!(line, " align=0x{v:x}")?;
+            }
+            if o.split {
+                line.push_str(" split");
+            }
+            if let Some(v) = o.hashcode {
+                write!(line, " hashcode=0x{v:x}")?;
+            }
+            if let Some(v) = o.version {
+                write!(line, " version={v}")?;
+            }
+            if let Some(v) = o.flags {
+                write!(line, " flags=0x{v:x}")?;
+            }
+            if let Some(v) = o.length {
+                write!(line, " length=0x{v:x}")?;
+            }
+            for loc in &o.fileloc {
+                write!(line, " loc={}:0x{:x}", loc.filelist_num, loc.addr)?;
+            }
+
+            writeln!(f, "{line}")?;
+        }
+
+        for (name, lines) in &self.other_sections {
+            writeln!(f, "\n[{name}]")?;
+            for l in lines {
+                writeln!(f, "{l}")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn parse_entry(line: &str) -> std::result::Result<ScrEntry, String> {
+    let (pattern, options) = if let Some(quoted) = line.strip_prefix('"') {
+        let end = quoted
+            .find('"')
+            .ok_or_else(|| "Unterminated quoted path".to_string())?;
+        (&quoted[..end], &quoted[end + 1..])
+    } else {
+        // Everything before the trailing run of options is part of the path, which may contain whitespace
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let num_options = tokens.iter().rev().take_while(|t| is_option(t)).count();
+        let last_path_token = tokens[tokens.len().saturating_sub(num_options + 1)];
+        let end =
+            last_path_token.as_ptr() as usize - line.as_ptr() as usize + last_path_token.len();
+        (&line[..end], &line[end..])
+    };
+
+    let mut entry = ScrEntry {
+        pattern: pattern.to_string(),
+        options: ScrFileOptions::default(),
+    };
+
+    let o = &mut entry.options;
+    for option in options.split_whitespace() {
+        let (key, value) = option.split_once('=').unwrap_or((option, ""));
+        match key.to_ascii_lowercase().as_str() {
+            "align" => o.alignment = Some(parse_number(value)?),
+            "split" => o.split = true,
+            "hashcode" => o.hashcode = Some(parse_number(value)?),
+            "version" => o.version = Some(parse_number(value)?),
+            "flags" => o.flags = Some(parse_number(value)?),
+            "length" => o.length = Some(parse_number(value)?),
+            "loc" => {
+                let (filelist_num, addr) = value
+                    .split_once(':')
+                    .ok_or_else(|| format!("Expected loc=<data file>:<address>, got '{value}'"))?;
+                o.fileloc.push(FileLoc5 {
+                    addr: parse_number(addr)?,
+                    filelist_num: parse_number(filelist_num)?,
+                });
+            }
+            _ => return Err(format!("Unknown file option '{key}'")),
+        }
+    }
+
+    Ok(entry)
+}
+
+/// Checks whether a token is a per-file option, see [`parse_entry`]
+fn is_option(token: &str) -> bool {
+    match token.split_once('=') {
+        Some((key, _)) => ["align", "hashcode", "version", "flags", "length", "loc"]
+            .iter()
+            .any(|k| k.eq_ignore_ascii_case(key)),
+        None => token.eq_ignore_ascii_case("split"),
+    }
+}
+
+fn parse_number<T: TryFrom<u64>>(value: &str) -> std::result::Result<T, String> {
+    let parsed = if let Some(hex) = value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        u64::from_str_radix(hex, 16)
+    } else {
+        value.parse()
+    };
+
+    parsed
+        .ok()
+        .and_then(|v| T::try_from(v).ok())
+        .ok_or_else(|| format!("Invalid number '{value}'"))
+}
+
+/// Matches a filelist path against an SCR wildcard pattern.
+///
+/// * `*` matches any number of characters within a single directory
+/// * `#` and `?` match exactly one character
+///
+/// Matching is case-insensitive, '/' and '\\' are treated as the same separator and drive letters are ignored.
+pub fn glob_match(pattern: &str, path: &str) -> bool {
+    let pattern = normalize_path(pattern);
+    let path = normalize_path(path);
+
+    glob_match_inner(pattern.as_bytes(), path.as_bytes())
+}
+
+fn glob_match_inner(pattern: &[u8], path: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((b'*', rest)) => {
+            // Try every possible length that doesn't cross a directory separator
+            for i in 0..=path.len() {
+                if glob_match_inner(rest, &path[i..]) {
+                    return true;
+                }
+
+                if i < path.len() && path[i] == b'\\' {
+                    break;
+                }
+            }
+
+            false
+        }
+        Some((b'#' | b'?', rest)) => match path.split_first() {
+            Some((c, path_rest)) if *c != b'\\' => glob_match_inner(rest, path_rest),
+            _ => false,
+        },
+        Some((c, rest)) => match path.split_first() {
+            Some((p, path_rest)) if p == c => glob_match_inner(rest, path_rest),
+            _ => false,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wildcards() {
+        assert!(glob_match(
+            "x:\\gforce\\binary\\_bin_pc\\*.edb",
+            "X:\\GForce\\Binary\\_bin_pc\\mw_intobj.edb"
+        ));
+        assert!(glob_match(
+            "x:/gforce/binary/_bin_pc/mw_int###.edb",
+            "x:\\gforce\\binary\\_bin_pc\\mw_intobj.edb"
+        ));
+        assert!(!glob_match(
+            "x:\\gforce\\*.edb",
+            "x:\\gforce\\binary\\_bin_pc\\mw_intobj.edb"
+        ));
+        assert!(!glob_match(
+            "x:\\gforce\\binary\\_bin_pc\\mw_int#.edb",
+            "x:\\gforce\\binary\\_bin_pc\\mw_intobj.edb"
+        ));
+    }
+
+    #[test]
+    fn roundtrip() {
+        let input = "[FileInfomation]
+Version=7
+BuildType=1
+Endian=Little
+SplitSize=0x80000000
+DataFileSizes=0x1800,0x800
+Unknown=1
+
+[FileList]
+x:\\gforce\\binary\\_bin_pc\\*.edb align=0x800
+x:\\gforce\\binary\\_bin_pc\\mw_intobj.edb split hashcode=0x80000001 version=259 flags=0x0 length=0x400
+\"x:\\gforce\\some file.txt\" loc=0:0x1000 loc=1:0x0
+
+[Custom]
+Some=Thing
+";
+
+        let scr = ScrFile::parse(input).unwrap();
+        assert_eq!(scr.info.version, Some(7));
+        assert_eq!(scr.info.data_file_sizes, vec![0x1800, 0x800]);
+        assert_eq!(scr.entries.len(), 3);
+        assert!(scr.entries[0].is_wildcard());
+        assert!(scr.entries[1].options.split);
+        assert_eq!(scr.entries[2].pattern, "x:\\gforce\\some file.txt");
+        assert_eq!(scr.entries[2].options.fileloc.len(), 2);
+
+        assert_eq!(scr.to_string(), input);
+    }
+
+    #[test]
+    fn plain_scr() {
+        let input = "Generated file list
+[FileList]
+x:\\gforce\\some dir\\some file.txt
+x:\\gforce\\some dir\\other file.txt align=0x800 split
+x:\\gforce\\binary\\_bin_pc\\mw_intobj.edb
+";
+
+        let scr = ScrFile::parse(input).unwrap();
+        assert_eq!(scr.entries.len(), 3);
+        assert_eq!(
+            scr.entries[0].pattern,
+            "x:\\gforce\\some dir\\some file.txt"
+        );
+        assert_eq!(scr.entries[0].options, ScrFileOptions::default());
+        assert_eq!(
+            scr.entries[1].pattern,
+            "x:\\gforce\\some dir\\other file.txt"
+        );
+        assert_eq!(scr.entries[1].options.alignment, Some(0x800));
+        assert!(scr.entries[1].options.split);
+        assert_eq!(
+            scr.entries[2].pattern,
+            "x:\\gforce\\binary\\_bin_pc\\mw_intobj.edb"
+        );
+
+        assert!(matches!(
+            ScrFile::parse("[FileList]\n\"x:\\file.txt\" unknown=1"),
+            Err(FilelistError::ScrParse { line: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn resolve_order() {
+        let scr = ScrFile::parse(
+            "[FileList]
+x:\\game\\b.edb
+x:\\game\\*.edb
+x:\\game\\a.edb
+x:\\game\\b.edb
+x:\\game\\missing.edb
+",
+        )
+        .unwrap();
+
+        let paths = ["x:\\game\\a.edb", "X:\\Game\\B.edb", "x:\\game\\c.edb"].map(String::from);
+        let resolved: Vec<String> = scr
+            .resolve(&paths)
+            .into_iter()
+            .map(|(_, path)| path)
+            .collect();
+
+        // Explicit entries for paths matched earlier are skipped, missing explicit entries are kept
+        assert_eq!(
+            resolved,
+            [
+                "x:\\game\\b.edb",
+                "x:\\game\\a.edb",
+                "x:\\game\\c.edb",
+                "x:\\game\\missing.edb",
+            ]
+        );
+    }
+}
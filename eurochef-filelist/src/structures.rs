@@ -58,7 +58,7 @@ pub struct FileInfo5 {
 }
 
 #[binrw]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct FileLoc5 {
     pub addr: u32,
     pub filelist_num: u32,
@@ -80,7 +80,7 @@ pub struct EXFileListHeader9 {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct FileInfo9 {
     pub unk0: u32,
     pub hashcode: u32,
@@ -1,11 +1,24 @@
-use std::io::{Read, Seek};
+use std::io::{Cursor, Read, Seek, SeekFrom, Write};
 
-use anyhow::Result;
-use binrw::{BinReaderExt, Endian};
+use binrw::{BinRead, BinReaderExt, BinWriterExt, Endian};
+use itertools::Itertools;
 
+use crate::error::{FilelistError, Result, UnsupportedError};
+use crate::structures::FileLoc5;
 use crate::{EXFileList4, EXFileList5, EXFileList9};
 
+/// Common interface for the versioned filelist formats
+pub trait EXFileList: Sized + Into<UXFileList> {
+    fn read<R: Read + Seek>(reader: &mut R) -> Result<Self>;
+    fn write<W: Write + Seek>(&self, writer: &mut W) -> Result<()>;
+
+    /// Converts a unified filelist to this format, using `filelist.version` as the version
+    fn from_unified(filelist: &UXFileList) -> Result<Self>;
+}
+
+#[derive(Debug, Clone)]
 pub struct UXFileList {
+    pub version: u32,
     /// `None` when using a single '.dat' file
     pub num_filelists: Option<u16>,
     pub build_type: Option<u16>,
@@ -13,31 +26,77 @@ pub struct UXFileList {
     pub files: Vec<(String, UXFileInfo)>,
 }
 
+#[derive(Debug, Clone)]
 pub struct UXFileInfo {
-    pub addr: u32,
-    pub filelist_num: Option<u32>,
+    /// Every location the file is stored at. Files can be stored multiple times to reduce seek times on disc-based platforms
+    pub fileloc: Vec<FileLoc5>,
 
     pub length: u32,
     pub hashcode: u32,
     pub version: u32,
     pub flags: u32,
-    // ? Should we consider multiple filelocs?
+
+    /// Unknown field of version 9+ file info entries, kept so headers can be written back unchanged
+    pub unk0: u32,
+    /// Unknown field of each version 9+ file location, indexed like `fileloc`
+    pub fileloc_unk0: Vec<u32>,
+}
+
+impl UXFileInfo {
+    /// Creates the file info for a file with the given (virtual) path and data, without any locations.
+    ///
+    /// The hashcode, version and flags are read from the header of geo (.edb) and sound (.sfx) files, other files use `default_hashcode`.
+    /// Geo files use their base filesize as length
+    pub fn from_data(
+        path: &str,
+        data: &[u8],
+        endian: Endian,
+        default_hashcode: u32,
+    ) -> Result<Self> {
+        let mut reader = Cursor::new(data);
+        let path = path.to_ascii_lowercase();
+
+        let mut length = data.len() as u32;
+        let (hashcode, version, flags) = if path.ends_with(".edb") {
+            // Use base filesize instead of full filesize
+            reader.seek(SeekFrom::Start(0x18))?;
+            length = reader.read_type(endian)?;
+
+            reader.seek(SeekFrom::Start(4))?;
+            (
+                reader.read_type(endian)?,
+                reader.read_type(endian)?,
+                reader.read_type(endian)?,
+            )
+        } else if path.ends_with(".sfx") {
+            reader.seek(SeekFrom::Start(4))?;
+            (
+                reader.read_le::<u32>()? | 0x21000000,
+                reader.read_type::<u8>(endian)? as u32,
+                0,
+            )
+        } else {
+            (default_hashcode, 0, 0)
+        };
+
+        Ok(Self {
+            fileloc: vec![],
+            length,
+            hashcode,
+            version,
+            flags,
+            unk0: 0,
+            fileloc_unk0: vec![],
+        })
+    }
 }
 
-// TODO: We should probably have our own error types, considering that this is a library
 impl UXFileList {
     pub fn read<R>(reader: &mut R) -> Result<Self>
     where
         R: Read + Seek,
     {
-        let marker: u8 = reader.read_ne()?;
-        let endian = if marker == 0 {
-            Endian::Big
-        } else {
-            Endian::Little
-        };
-        reader.seek(std::io::SeekFrom::Start(0))?;
-
+        let endian = detect_endian(reader)?;
         Self::read_endian(reader, endian)
     }
 
@@ -45,14 +104,303 @@ impl UXFileList {
     where
         R: Read + Seek,
     {
-        let version: u32 = reader.read_type(endian)?;
+        let version: u32 = read_header(reader, endian)?;
         reader.seek(std::io::SeekFrom::Start(0))?;
 
         Ok(match version {
             4 => EXFileList4::read(reader)?.into(),
             5..=7 => EXFileList5::read(reader)?.into(),
             9..=10 => EXFileList9::read(reader)?.into(),
-            v => return Err(anyhow::anyhow!("Unsupported filelist version {}", v)),
+            v => {
+                return Err(FilelistError::Unsupported(UnsupportedError::Version(v)));
+            }
         })
     }
+
+    /// Writes the filelist header using the format matching `self.version`
+    pub fn write<W>(&self, writer: &mut W) -> Result<()>
+    where
+        W: Write + Seek,
+    {
+        match self.version {
+            4 => EXFileList4::from_unified(self)?.write(writer),
+            5..=7 => EXFileList5::from_unified(self)?.write(writer),
+            9..=10 => EXFileList9::from_unified(self)?.write(writer),
+            v => Err(FilelistError::Unsupported(UnsupportedError::Version(v))),
+        }
+    }
+}
+
+/// Version 4 filelists only support a single .DAT file, later versions use numbered data files (.000, .001, ...)
+pub fn has_single_data_file(version: u32) -> bool {
+    version == 4
+}
+
+/// Detects the endianness of a filelist header from the first byte of the version number and rewinds the reader
+pub(crate) fn detect_endian<R>(reader: &mut R) -> Result<Endian>
+where
+    R: Read + Seek,
+{
+    reader.seek(SeekFrom::Start(0))?;
+    let marker: u8 = read_header(reader, Endian::Little)?;
+    reader.seek(SeekFrom::Start(0))?;
+
+    Ok(if marker == 0 {
+        Endian::Big
+    } else {
+        Endian::Little
+    })
+}
+
+/// Reads a header structure, reporting running out of data as a truncated header
+pub(crate) fn read_header<R, T>(reader: &mut R, endian: Endian) -> Result<T>
+where
+    R: Read + Seek,
+    T: BinRead,
+    for<'a> T::Args<'a>: Default + Clone,
+{
+    reader.read_type(endian).map_err(|e| {
+        if e.is_eof() {
+            FilelistError::TruncatedHeader
+        } else {
+            e.into()
+        }
+    })
+}
+
+/// Reads the filename table located at `table_offset` and the filenames it points to
+///
+/// `sorted` sorts the filename offsets before reading, the index passed to `unscramble` is the index in sorted order
+pub(crate) fn read_filenames<R, F>(
+    reader: &mut R,
+    endian: Endian,
+    table_offset: u64,
+    num_files: usize,
+    filesize: u64,
+    sorted: bool,
+    unscramble: F,
+) -> Result<Vec<String>>
+where
+    R: Read + Seek,
+    F: Fn(u32, &mut [u8]) -> Result<()>,
+{
+    reader.seek(SeekFrom::Start(table_offset))?;
+
+    let mut filename_offsets = vec![];
+    for i in 0..num_files as u64 {
+        let offset = read_header::<_, u32>(reader, endian)? as u64 + table_offset + i * 4;
+        if offset >= filesize {
+            return Err(FilelistError::BadFilenameOffset {
+                index: i as usize,
+                offset,
+            });
+        }
+
+        filename_offsets.push(offset);
+    }
+
+    if sorted {
+        filename_offsets.sort();
+    }
+
+    let mut filenames = vec![];
+    // FIXME: If the strings arent encoded linearly this will be a bit inefficient
+    for (i, (start, end)) in filename_offsets
+        .iter()
+        .chain([filesize].iter())
+        .tuple_windows()
+        .enumerate()
+    {
+        if end < start {
+            return Err(FilelistError::BadFilenameOffset {
+                index: i,
+                offset: *start,
+            });
+        }
+
+        let mut data = vec![0u8; (end - start) as usize];
+        reader.seek(SeekFrom::Start(*start))?;
+        reader.read_exact(&mut data).map_err(|e| {
+            if e.kind() == std::io::ErrorKind::UnexpectedEof {
+                FilelistError::TruncatedHeader
+            } else {
+                e.into()
+            }
+        })?;
+
+        unscramble(i as u32, &mut data)?;
+
+        let null_pos = data.iter().position(|&p| p == 0).unwrap_or(data.len());
+        filenames.push(String::from_utf8_lossy(&data[0..null_pos]).to_string());
+    }
+
+    Ok(filenames)
+}
+
+/// Writes the filename table and filenames following the header, pads the file and patches the filesize and filename list offset in the header
+pub(crate) fn write_filenames<W, F>(
+    writer: &mut W,
+    endian: Endian,
+    filenames: &[String],
+    filename_list_offset_pos: u64,
+    scramble: F,
+) -> Result<()>
+where
+    W: Write + Seek,
+    F: Fn(u32, &mut [u8]) -> Result<()>,
+{
+    let filename_offset = writer.stream_position()?;
+    let filename_data_offset = filename_offset + filenames.len() as u64 * 4;
+
+    let mut offset = filename_data_offset;
+    for (i, v) in filenames.iter().enumerate() {
+        let ptr_offset = filename_offset + i as u64 * 4;
+        writer.write_type(&((offset - ptr_offset) as u32), endian)?;
+        offset += v.len() as u64 + 1;
+    }
+
+    for (i, v) in filenames.iter().enumerate() {
+        let mut path_buf = v.as_bytes().to_vec();
+        path_buf.push(0);
+        scramble(i as u32, &mut path_buf)?;
+        writer.write_all(&path_buf)?;
+    }
+
+    // Pad the file to 32 bytes
+    let unaligned_size = writer.stream_position()?;
+    if unaligned_size & 0x1f != 0 {
+        let remainder = 32 - (unaligned_size % 32);
+        writer.write_all(&vec![0u8; remainder as usize])?;
+    }
+
+    let file_size = writer.stream_position()?;
+
+    writer.seek(SeekFrom::Start(4))?;
+    writer.write_type(&(file_size as u32), endian)?;
+
+    writer.seek(SeekFrom::Start(filename_list_offset_pos))?;
+    writer.write_type(
+        &((filename_offset - filename_list_offset_pos) as u32),
+        endian,
+    )?;
+
+    writer.seek(SeekFrom::Start(file_size))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::path::{scramble_filename_v7, unscramble_filename_v7};
+
+    /// Writes a little endian version 7 filelist with 2 files
+    fn test_filelist_data() -> Vec<u8> {
+        let filelist = UXFileList {
+            version: 7,
+            num_filelists: Some(0),
+            build_type: Some(1),
+            endian: Endian::Little,
+            files: (0..2)
+                .map(|i| {
+                    (
+                        format!("x:\\game\\file{i}.edb"),
+                        UXFileInfo {
+                            fileloc: vec![FileLoc5 {
+                                addr: i * 0x800,
+                                filelist_num: 0,
+                            }],
+                            length: 0x100,
+                            hashcode: 0x81000000 | i,
+                            version: 0,
+                            flags: 0,
+                            unk0: 0,
+                            fileloc_unk0: vec![],
+                        },
+                    )
+                })
+                .collect(),
+        };
+
+        let mut data = Cursor::new(vec![]);
+        filelist.write(&mut data).unwrap();
+        data.into_inner()
+    }
+
+    #[test]
+    fn truncated_header() {
+        let data = test_filelist_data();
+        assert!(UXFileList::read(&mut Cursor::new(&data)).is_ok());
+
+        // Cut off inside of the file info entries
+        assert!(matches!(
+            UXFileList::read(&mut Cursor::new(&data[..0x20])),
+            Err(FilelistError::TruncatedHeader)
+        ));
+
+        // Cut off inside of the filenames
+        assert!(matches!(
+            UXFileList::read(&mut Cursor::new(&data[..data.len() - 0x28])),
+            Err(FilelistError::TruncatedHeader)
+        ));
+
+        assert!(matches!(
+            UXFileList::read(&mut Cursor::new(&[7u8, 0])),
+            Err(FilelistError::TruncatedHeader)
+        ));
+    }
+
+    #[test]
+    fn bad_filename_offset() {
+        let mut data = test_filelist_data();
+        let table_offset = 0x10 + u32::from_le_bytes(data[0x10..0x14].try_into().unwrap()) as usize;
+
+        // Second filename pointing past the end of the header
+        let entry = table_offset + 4;
+        data[entry..entry + 4].copy_from_slice(&0x1000u32.to_le_bytes());
+
+        assert!(matches!(
+            UXFileList::read(&mut Cursor::new(&data)),
+            Err(FilelistError::BadFilenameOffset { index: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn unsupported_version() {
+        for version in [3u32, 8, 14] {
+            let mut data = test_filelist_data();
+            data[0..4].copy_from_slice(&version.to_le_bytes());
+
+            assert!(matches!(
+                UXFileList::read(&mut Cursor::new(&data)),
+                Err(FilelistError::Unsupported(UnsupportedError::Version(v))) if v == version
+            ));
+        }
+    }
+
+    #[test]
+    fn unterminated_filename() {
+        // A single filename table entry pointing right after itself, followed by a filename without a terminator
+        let mut filename = b"file0.edb\0".to_vec();
+        scramble_filename_v7(0, &mut filename).unwrap();
+        filename.pop();
+
+        let mut data = 4u32.to_le_bytes().to_vec();
+        data.extend(filename);
+        let filesize = data.len() as u64;
+
+        let result = read_filenames(
+            &mut Cursor::new(&data),
+            Endian::Little,
+            0,
+            1,
+            filesize,
+            true,
+            unscramble_filename_v7,
+        );
+        assert!(matches!(
+            result,
+            Err(FilelistError::UnterminatedFilename(0))
+        ));
+    }
 }
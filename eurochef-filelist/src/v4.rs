@@ -1,11 +1,12 @@
-use std::io::{Read, Seek};
+use std::io::{Read, Seek, Write};
 
-use crate::structures::EXFileListHeader4;
-use crate::unified::{UXFileInfo, UXFileList};
+use crate::error::{FilelistError, Result, UnsupportedError};
+use crate::structures::{EXFileListHeader4, FileInfo4, FileLoc5};
+use crate::unified::{
+    detect_endian, read_filenames, read_header, write_filenames, EXFileList, UXFileInfo, UXFileList,
+};
 
-use anyhow::Result;
-use binrw::{BinReaderExt, Endian};
-use itertools::Itertools;
+use binrw::{BinWriterExt, Endian};
 
 #[derive(Debug)]
 pub struct EXFileList4 {
@@ -14,59 +15,84 @@ pub struct EXFileList4 {
     pub filenames: Vec<String>,
 }
 
-impl EXFileList4 {
-    pub fn read<R>(reader: &mut R) -> Result<Self>
+impl EXFileList for EXFileList4 {
+    fn read<R>(reader: &mut R) -> Result<Self>
     where
         R: Read + Seek,
     {
-        let endian = if reader.read_ne::<u8>()? != 0 {
-            Endian::Little
-        } else {
-            Endian::Big
-        };
-        reader.seek(std::io::SeekFrom::Start(0))?;
-
-        let mut res = Self {
-            endian,
-            header: reader.read_type(endian)?,
-            filenames: vec![],
-        };
+        let endian = detect_endian(reader)?;
+        let header: EXFileListHeader4 = read_header(reader, endian)?;
 
         // TODO: RelPtr? (currently stuck in eurochef-edb)
-        reader.seek(std::io::SeekFrom::Start(
-            0xc + res.header.filename_list_offset as u64,
-        ))?;
-
-        let base_offset = reader.stream_position()?;
-        let mut filename_offsets = vec![];
-        for i in 0..res.header.fileinfo.len() as u64 {
-            filename_offsets.push(reader.read_type::<u32>(endian)? as u64 + base_offset + i * 4);
+        let filenames = read_filenames(
+            reader,
+            endian,
+            0xc + header.filename_list_offset as u64,
+            header.fileinfo.len(),
+            header.filesize as u64,
+            false,
+            |_, _| Ok(()),
+        )?;
+
+        Ok(Self {
+            endian,
+            header,
+            filenames,
+        })
+    }
+
+    fn write<W>(&self, writer: &mut W) -> Result<()>
+    where
+        W: Write + Seek,
+    {
+        writer.seek(std::io::SeekFrom::Start(0))?;
+        writer.write_type(&self.header, self.endian)?;
+
+        write_filenames(writer, self.endian, &self.filenames, 0xc, |_, _| Ok(()))
+    }
+
+    fn from_unified(filelist: &UXFileList) -> Result<Self> {
+        if filelist.version != 4 {
+            return Err(FilelistError::Unsupported(UnsupportedError::Conversion(
+                filelist.version,
+                "4",
+            )));
         }
 
-        // FIXME: If the strings arent encoded linearly this will be a bit inefficient
-        for (_, (start, end)) in filename_offsets
-            .iter()
-            .chain([res.header.filesize as u64].iter())
-            .tuple_windows()
-            .enumerate()
-        {
-            let size = end - start;
-            let mut data = vec![0u8; size as usize];
-            reader.seek(std::io::SeekFrom::Start(*start))?;
-            reader.read(&mut data)?;
-
-            let null_pos = data.iter().position(|&p| p == 0).unwrap_or(data.len());
-            res.filenames
-                .push(String::from_utf8_lossy(&data[0..null_pos]).to_string());
+        let mut fileinfo = vec![];
+        for (_, info) in &filelist.files {
+            if info.fileloc.len() != 1 {
+                return Err(FilelistError::Unsupported(
+                    UnsupportedError::MultipleLocations(4),
+                ));
+            }
+
+            fileinfo.push(FileInfo4 {
+                addr: info.fileloc[0].addr,
+                length: info.length,
+                hashcode: info.hashcode,
+                version: info.version,
+                flags: info.flags,
+            });
         }
 
-        Ok(res)
+        Ok(Self {
+            endian: filelist.endian,
+            header: EXFileListHeader4 {
+                version: filelist.version,
+                filesize: 0,
+                filename_list_offset: 0,
+                fileinfo,
+            },
+            filenames: filelist.files.iter().map(|(f, _)| f.clone()).collect(),
+        })
     }
 }
 
 impl Into<UXFileList> for EXFileList4 {
     fn into(self) -> UXFileList {
         UXFileList {
+            version: self.header.version,
             num_filelists: None,
             build_type: None,
             endian: self.endian,
@@ -78,16 +104,100 @@ impl Into<UXFileList> for EXFileList4 {
                     (
                         filename,
                         UXFileInfo {
-                            addr: info.addr,
-                            filelist_num: None,
+                            fileloc: vec![FileLoc5 {
+                                addr: info.addr,
+                                filelist_num: 0,
+                            }],
                             flags: info.flags,
                             hashcode: info.hashcode,
                             length: info.length,
                             version: info.version,
+                            unk0: 0,
+                            fileloc_unk0: vec![],
+                        },
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    fn test_filelist(endian: Endian) -> UXFileList {
+        UXFileList {
+            version: 4,
+            num_filelists: None,
+            build_type: None,
+            endian,
+            files: (0..3)
+                .map(|i| {
+                    (
+                        format!("x:\\sphinx\\binary\\_bin_pc\\file{i}.edb"),
+                        UXFileInfo {
+                            fileloc: vec![FileLoc5 {
+                                addr: i * 0x800,
+                                filelist_num: 0,
+                            }],
+                            length: 0x100 + i,
+                            hashcode: 0x81000000 | i,
+                            version: 182,
+                            flags: i,
+                            unk0: 0,
+                            fileloc_unk0: vec![],
                         },
                     )
                 })
                 .collect(),
         }
     }
+
+    #[test]
+    fn roundtrip() {
+        for endian in [Endian::Little, Endian::Big] {
+            let filelist = test_filelist(endian);
+            let mut data = Cursor::new(vec![]);
+            filelist.write(&mut data).unwrap();
+            let data = data.into_inner();
+
+            let read = UXFileList::read(&mut Cursor::new(&data)).unwrap();
+            assert_eq!(read.version, 4);
+            assert_eq!(read.num_filelists, None);
+            assert_eq!(read.build_type, None);
+            assert_eq!(read.endian, endian);
+            assert_eq!(read.files.len(), 3);
+            for ((path, info), (read_path, read_info)) in filelist.files.iter().zip(&read.files) {
+                assert_eq!(path, read_path);
+                assert_eq!(info.fileloc, read_info.fileloc);
+                assert_eq!(info.length, read_info.length);
+                assert_eq!(info.hashcode, read_info.hashcode);
+                assert_eq!(info.version, read_info.version);
+                assert_eq!(info.flags, read_info.flags);
+            }
+
+            let mut rewritten = Cursor::new(vec![]);
+            read.write(&mut rewritten).unwrap();
+            assert_eq!(rewritten.into_inner(), data);
+        }
+    }
+
+    #[test]
+    fn single_location_only() {
+        let mut filelist = test_filelist(Endian::Little);
+        filelist.files[1].1.fileloc.push(FileLoc5 {
+            addr: 0x2000,
+            filelist_num: 0,
+        });
+
+        assert!(matches!(
+            EXFileList4::from_unified(&filelist),
+            Err(FilelistError::Unsupported(
+                UnsupportedError::MultipleLocations(4)
+            ))
+        ));
+    }
 }
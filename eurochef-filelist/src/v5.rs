@@ -1,12 +1,13 @@
-use std::io::{Read, Seek};
+use std::io::{Read, Seek, Write};
 
-use crate::path::unscramble_filename_v7;
-use crate::structures::EXFileListHeader5;
-use crate::unified::{UXFileInfo, UXFileList};
+use crate::error::{FilelistError, Result, UnsupportedError};
+use crate::path::{scramble_filename_v7, unscramble_filename_v7};
+use crate::structures::{EXFileListHeader5, FileInfo5};
+use crate::unified::{
+    detect_endian, read_filenames, read_header, write_filenames, EXFileList, UXFileInfo, UXFileList,
+};
 
-use anyhow::Result;
-use binrw::{BinReaderExt, Endian};
-use itertools::Itertools;
+use binrw::{BinWriterExt, Endian};
 
 #[derive(Debug)]
 pub struct EXFileList5 {
@@ -15,65 +16,93 @@ pub struct EXFileList5 {
     pub filenames: Vec<String>,
 }
 
-impl EXFileList5 {
-    pub fn read<R>(reader: &mut R) -> Result<Self>
+impl EXFileList for EXFileList5 {
+    fn read<R>(reader: &mut R) -> Result<Self>
     where
         R: Read + Seek,
     {
-        let endian = if reader.read_ne::<u8>()? != 0 {
-            Endian::Little
-        } else {
-            Endian::Big
-        };
-        reader.seek(std::io::SeekFrom::Start(0))?;
-
-        let mut res = Self {
-            endian,
-            header: reader.read_type(endian)?,
-            filenames: vec![],
-        };
+        let endian = detect_endian(reader)?;
+        let header: EXFileListHeader5 = read_header(reader, endian)?;
 
         // TODO: RelPtr? (currently stuck in eurochef-edb)
-        reader.seek(std::io::SeekFrom::Start(
-            0x10 + res.header.filename_list_offset as u64,
-        ))?;
-
-        let base_offset = reader.stream_position()?;
-        let mut filename_offsets = vec![];
-        for i in 0..res.header.fileinfo.len() as u64 {
-            filename_offsets.push(reader.read_type::<u32>(endian)? as u64 + base_offset + i * 4);
-        }
+        let version = header.version;
+        let filenames = read_filenames(
+            reader,
+            endian,
+            0x10 + header.filename_list_offset as u64,
+            header.fileinfo.len(),
+            header.filesize as u64,
+            true,
+            |i, data| {
+                if version >= 7 {
+                    unscramble_filename_v7(i, data)
+                } else {
+                    Ok(())
+                }
+            },
+        )?;
+
+        Ok(Self {
+            endian,
+            header,
+            filenames,
+        })
+    }
 
-        filename_offsets.sort();
-        // FIXME: If the strings arent encoded linearly this will be a bit inefficient
-        for (i, (start, end)) in filename_offsets
-            .iter()
-            .chain([res.header.filesize as u64].iter())
-            .tuple_windows()
-            .enumerate()
-        {
-            let size = end - start;
-            let mut data = vec![0u8; size as usize];
-            reader.seek(std::io::SeekFrom::Start(*start))?;
-            reader.read(&mut data)?;
-
-            if res.header.version >= 7 {
-                unscramble_filename_v7(i as u32, &mut data);
+    fn write<W>(&self, writer: &mut W) -> Result<()>
+    where
+        W: Write + Seek,
+    {
+        writer.seek(std::io::SeekFrom::Start(0))?;
+        writer.write_type(&self.header, self.endian)?;
+
+        let version = self.header.version;
+        write_filenames(writer, self.endian, &self.filenames, 0x10, |i, data| {
+            if version >= 7 {
+                scramble_filename_v7(i, data)
+            } else {
+                Ok(())
             }
+        })
+    }
 
-            let null_pos = data.iter().position(|&p| p == 0).unwrap_or(data.len());
-            res.filenames
-                .push(String::from_utf8_lossy(&data[0..null_pos]).to_string());
+    fn from_unified(filelist: &UXFileList) -> Result<Self> {
+        if !(5..=7).contains(&filelist.version) {
+            return Err(FilelistError::Unsupported(UnsupportedError::Conversion(
+                filelist.version,
+                "5-7",
+            )));
         }
 
-        Ok(res)
+        Ok(Self {
+            endian: filelist.endian,
+            header: EXFileListHeader5 {
+                version: filelist.version,
+                filesize: 0,
+                build_type: filelist.build_type.unwrap_or(1),
+                num_filelists: filelist.num_filelists.unwrap_or(0),
+                filename_list_offset: 0,
+                fileinfo: filelist
+                    .files
+                    .iter()
+                    .map(|(_, info)| FileInfo5 {
+                        length: info.length,
+                        hashcode: info.hashcode,
+                        version: info.version,
+                        flags: info.flags,
+                        fileloc: info.fileloc.clone(),
+                    })
+                    .collect(),
+            },
+            filenames: filelist.files.iter().map(|(f, _)| f.clone()).collect(),
+        })
     }
 }
 
-// TODO: Make a trait for filelists bundling both the read and from/into functions so that they can be used genericly
 impl Into<UXFileList> for EXFileList5 {
     fn into(self) -> UXFileList {
         UXFileList {
+            version: self.header.version,
             num_filelists: Some(self.header.num_filelists),
             build_type: Some(self.header.build_type),
             endian: self.endian,
@@ -85,16 +114,135 @@ impl Into<UXFileList> for EXFileList5 {
                     (
                         filename,
                         UXFileInfo {
-                            addr: info.fileloc[0].addr,
-                            filelist_num: Some(info.fileloc[0].filelist_num),
+                            fileloc: info.fileloc,
                             flags: info.flags,
                             hashcode: info.hashcode,
                             length: info.length,
                             version: info.version,
+                            unk0: 0,
+                            fileloc_unk0: vec![],
+                        },
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::structures::FileLoc5;
+
+    fn test_filelist(version: u32, endian: Endian) -> UXFileList {
+        UXFileList {
+            version,
+            num_filelists: Some(1),
+            build_type: Some(2),
+            endian,
+            files: (0..3)
+                .map(|i| {
+                    (
+                        format!("x:\\gforce\\binary\\_bin_pc\\file{i}.edb"),
+                        UXFileInfo {
+                            fileloc: vec![FileLoc5 {
+                                addr: i * 0x800,
+                                filelist_num: i % 2,
+                            }],
+                            length: 0x100 + i,
+                            hashcode: 0x81000000 | i,
+                            version: 259,
+                            flags: i,
+                            unk0: 0,
+                            fileloc_unk0: vec![],
                         },
                     )
                 })
                 .collect(),
         }
     }
+
+    #[test]
+    fn roundtrip() {
+        for (version, endian) in [
+            (5, Endian::Little),
+            (6, Endian::Big),
+            (7, Endian::Little),
+            (7, Endian::Big),
+        ] {
+            let filelist = test_filelist(version, endian);
+            let mut data = Cursor::new(vec![]);
+            filelist.write(&mut data).unwrap();
+            let data = data.into_inner();
+
+            let read = UXFileList::read(&mut Cursor::new(&data)).unwrap();
+            assert_eq!(read.version, version);
+            assert_eq!(read.num_filelists, Some(1));
+            assert_eq!(read.build_type, Some(2));
+            assert_eq!(read.endian, endian);
+            assert_eq!(read.files.len(), 3);
+            for ((path, info), (read_path, read_info)) in filelist.files.iter().zip(&read.files) {
+                assert_eq!(path, read_path);
+                assert_eq!(info.fileloc, read_info.fileloc);
+                assert_eq!(info.length, read_info.length);
+                assert_eq!(info.hashcode, read_info.hashcode);
+                assert_eq!(info.version, read_info.version);
+                assert_eq!(info.flags, read_info.flags);
+            }
+
+            let mut rewritten = Cursor::new(vec![]);
+            read.write(&mut rewritten).unwrap();
+            assert_eq!(rewritten.into_inner(), data);
+        }
+    }
+
+    #[test]
+    fn v7_scrambles_filenames() {
+        for version in 5..=7 {
+            let filelist =
+                EXFileList5::from_unified(&test_filelist(version, Endian::Little)).unwrap();
+            let mut data = Cursor::new(vec![]);
+            filelist.write(&mut data).unwrap();
+            let data = data.into_inner();
+
+            let read = EXFileList5::read(&mut Cursor::new(&data)).unwrap();
+            assert_eq!(read.filenames, filelist.filenames);
+
+            // Filenames directly follow the offset table
+            let filename_data = 0x10 + read.header.filename_list_offset as usize + 3 * 4;
+            let mut expected = b"x:\\gforce\\binary\\_bin_pc\\file0.edb\0".to_vec();
+            if version == 7 {
+                scramble_filename_v7(0, &mut expected).unwrap();
+            }
+
+            assert_eq!(
+                &data[filename_data..filename_data + expected.len()],
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn duplicate_locations_roundtrip() {
+        let mut filelist = test_filelist(6, Endian::Little);
+        for (i, (_, info)) in filelist.files.iter_mut().enumerate() {
+            info.fileloc = (0..=i as u32)
+                .map(|copy| FileLoc5 {
+                    addr: 0x10000 * copy + 0x800 * i as u32,
+                    filelist_num: copy,
+                })
+                .collect();
+        }
+
+        let mut data = Cursor::new(vec![]);
+        filelist.write(&mut data).unwrap();
+        let read = UXFileList::read(&mut Cursor::new(data.into_inner())).unwrap();
+
+        for ((_, info), (_, read_info)) in filelist.files.iter().zip(&read.files) {
+            assert_eq!(info.fileloc, read_info.fileloc);
+        }
+        assert_eq!(read.files[2].1.fileloc.len(), 3);
+    }
 }
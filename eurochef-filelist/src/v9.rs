@@ -1,12 +1,15 @@
-use std::io::{Read, Seek};
+use std::io::{Read, Seek, Write};
 
-use crate::path::{unscramble_filename_v10, unscramble_filename_v7};
-use crate::structures::EXFileListHeader9;
-use crate::unified::{UXFileInfo, UXFileList};
+use crate::error::{FilelistError, Result, UnsupportedError};
+use crate::path::{
+    scramble_filename_v10, scramble_filename_v7, unscramble_filename_v10, unscramble_filename_v7,
+};
+use crate::structures::{EXFileListHeader9, FileInfo9, FileLoc5, FileLoc9};
+use crate::unified::{
+    detect_endian, read_filenames, read_header, write_filenames, EXFileList, UXFileInfo, UXFileList,
+};
 
-use anyhow::Result;
-use binrw::{BinReaderExt, Endian};
-use itertools::Itertools;
+use binrw::{BinWriterExt, Endian};
 
 #[derive(Debug)]
 pub struct EXFileList9 {
@@ -15,67 +18,103 @@ pub struct EXFileList9 {
     pub filenames: Vec<String>,
 }
 
-impl EXFileList9 {
-    pub fn read<R>(reader: &mut R) -> Result<Self>
+impl EXFileList for EXFileList9 {
+    fn read<R>(reader: &mut R) -> Result<Self>
     where
         R: Read + Seek,
     {
-        let endian = if reader.read_ne::<u8>()? != 0 {
-            Endian::Little
-        } else {
-            Endian::Big
-        };
-        reader.seek(std::io::SeekFrom::Start(0))?;
-
-        let mut res = Self {
-            endian,
-            header: reader.read_type(endian)?,
-            filenames: vec![],
-        };
+        let endian = detect_endian(reader)?;
+        let header: EXFileListHeader9 = read_header(reader, endian)?;
 
         // TODO: RelPtr? (currently stuck in eurochef-edb)
-        reader.seek(std::io::SeekFrom::Start(
-            0x10 + res.header.filename_list_offset as u64,
-        ))?;
-
-        let base_offset = reader.stream_position()?;
-        let mut filename_offsets = vec![];
-        for i in 0..res.header.fileinfo.len() as u64 {
-            filename_offsets.push(reader.read_type::<u32>(endian)? as u64 + base_offset + i * 4);
-        }
+        let version = header.version;
+        let filenames = read_filenames(
+            reader,
+            endian,
+            0x10 + header.filename_list_offset as u64,
+            header.fileinfo.len(),
+            header.filesize as u64,
+            true,
+            |i, data| {
+                if version >= 10 {
+                    unscramble_filename_v10(i, data)
+                } else {
+                    unscramble_filename_v7(i, data)
+                }
+            },
+        )?;
+
+        Ok(Self {
+            endian,
+            header,
+            filenames,
+        })
+    }
 
-        filename_offsets.sort();
-        // FIXME: If the strings arent encoded linearly this will be a bit inefficient
-        for (i, (start, end)) in filename_offsets
-            .iter()
-            .chain([res.header.filesize as u64].iter())
-            .tuple_windows()
-            .enumerate()
-        {
-            let size = end - start;
-            let mut data = vec![0u8; size as usize];
-            reader.seek(std::io::SeekFrom::Start(*start))?;
-            reader.read(&mut data)?;
-
-            if res.header.version >= 10 {
-                unscramble_filename_v10(i as u32, &mut data);
+    fn write<W>(&self, writer: &mut W) -> Result<()>
+    where
+        W: Write + Seek,
+    {
+        writer.seek(std::io::SeekFrom::Start(0))?;
+        writer.write_type(&self.header, self.endian)?;
+
+        let version = self.header.version;
+        write_filenames(writer, self.endian, &self.filenames, 0x10, |i, data| {
+            if version >= 10 {
+                scramble_filename_v10(i, data)
             } else {
-                unscramble_filename_v7(i as u32, &mut data);
+                scramble_filename_v7(i, data)
             }
+        })
+    }
 
-            let null_pos = data.iter().position(|&p| p == 0).unwrap_or(data.len());
-            res.filenames
-                .push(String::from_utf8_lossy(&data[0..null_pos]).to_string());
+    fn from_unified(filelist: &UXFileList) -> Result<Self> {
+        if !(9..=10).contains(&filelist.version) {
+            return Err(FilelistError::Unsupported(UnsupportedError::Conversion(
+                filelist.version,
+                "9-10",
+            )));
         }
 
-        Ok(res)
+        Ok(Self {
+            endian: filelist.endian,
+            header: EXFileListHeader9 {
+                version: filelist.version,
+                filesize: 0,
+                build_type: filelist.build_type.unwrap_or(1),
+                num_filelists: filelist.num_filelists.unwrap_or(0),
+                filename_list_offset: 0,
+                fileinfo: filelist
+                    .files
+                    .iter()
+                    .map(|(_, info)| FileInfo9 {
+                        unk0: info.unk0,
+                        hashcode: info.hashcode,
+                        version: info.version,
+                        flags: info.flags,
+                        length: info.length,
+                        fileloc: info
+                            .fileloc
+                            .iter()
+                            .enumerate()
+                            .map(|(i, loc)| FileLoc9 {
+                                unk0: info.fileloc_unk0.get(i).copied().unwrap_or(0),
+                                addr: loc.addr,
+                                filelist_num: loc.filelist_num,
+                            })
+                            .collect(),
+                    })
+                    .collect(),
+            },
+            filenames: filelist.files.iter().map(|(f, _)| f.clone()).collect(),
+        })
     }
 }
 
-// TODO: Make a trait for filelists bundling both the read and from/into functions so that they can be used genericly
 impl Into<UXFileList> for EXFileList9 {
     fn into(self) -> UXFileList {
         UXFileList {
+            version: self.header.version,
             num_filelists: Some(self.header.num_filelists),
             build_type: Some(self.header.build_type),
             endian: self.endian,
@@ -87,12 +126,20 @@ impl Into<UXFileList> for EXFileList9 {
                     (
                         filename,
                         UXFileInfo {
-                            addr: info.fileloc[0].addr,
-                            filelist_num: Some(info.fileloc[0].filelist_num),
+                            fileloc_unk0: info.fileloc.iter().map(|loc| loc.unk0).collect(),
+                            fileloc: info
+                                .fileloc
+                                .into_iter()
+                                .map(|loc| FileLoc5 {
+                                    addr: loc.addr,
+                                    filelist_num: loc.filelist_num,
+                                })
+                                .collect(),
                             flags: info.flags,
                             hashcode: info.hashcode,
                             length: info.length,
                             version: info.version,
+                            unk0: info.unk0,
                         },
                     )
                 })
@@ -100,3 +147,80 @@ impl Into<UXFileList> for EXFileList9 {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn roundtrip_preserves_unknowns() {
+        for (version, endian) in [(9, Endian::Little), (10, Endian::Big)] {
+            let filelist = EXFileList9 {
+                endian,
+                header: EXFileListHeader9 {
+                    version,
+                    filesize: 0,
+                    build_type: 1,
+                    num_filelists: 2,
+                    filename_list_offset: 0,
+                    fileinfo: (0..4)
+                        .map(|i| FileInfo9 {
+                            unk0: 0xdead0000 | i,
+                            hashcode: 0x81000000 | i,
+                            version: 1,
+                            flags: 0,
+                            length: 0x100 + i,
+                            fileloc: vec![
+                                FileLoc9 {
+                                    unk0: 0xbeef0000 | i,
+                                    addr: i * 0x800,
+                                    filelist_num: i % 2,
+                                },
+                                FileLoc9 {
+                                    unk0: 0xcafe0000 | i,
+                                    addr: i * 0x800,
+                                    filelist_num: 2,
+                                },
+                            ],
+                        })
+                        .collect(),
+                },
+                filenames: (0..4)
+                    .map(|i| format!("x:\\game\\binary\\_bin_pc\\file{i}.edb"))
+                    .collect(),
+            };
+
+            let mut data = Cursor::new(vec![]);
+            filelist.write(&mut data).unwrap();
+            let data = data.into_inner();
+
+            // Going through the unified representation should not lose any fields
+            let unified = UXFileList::read(&mut Cursor::new(&data)).unwrap();
+            assert_eq!(unified.files[3].1.unk0, 0xdead0003);
+            assert_eq!(unified.files[3].1.fileloc_unk0, [0xbeef0003, 0xcafe0003]);
+
+            let mut rewritten = Cursor::new(vec![]);
+            unified.write(&mut rewritten).unwrap();
+            assert_eq!(rewritten.into_inner(), data);
+        }
+    }
+
+    #[test]
+    fn unverified_versions_unsupported() {
+        for version in 11..=13u32 {
+            let mut data = version.to_le_bytes().to_vec();
+            data.resize(0x40, 0);
+            assert!(matches!(
+                UXFileList::read(&mut Cursor::new(&data)),
+                Err(FilelistError::Unsupported(UnsupportedError::Version(v))) if v == version
+            ));
+
+            assert!(matches!(
+                crate::builder::FilelistBuilder::new(version, Endian::Little),
+                Err(FilelistError::Unsupported(UnsupportedError::Version(v))) if v == version
+            ));
+        }
+    }
+}
@@ -0,0 +1,401 @@
+//! Structural comparison of two filelists, used to check that a rebuilt filelist matches the original
+
+use std::{fmt::Display, io::Cursor};
+
+use binrw::Endian;
+
+use crate::{error::Result, structures::FileLoc5, unified::UXFileList, FilelistVfs};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Divergence {
+    /// A header field differs
+    Header {
+        field: &'static str,
+        original: String,
+        rebuilt: String,
+    },
+    /// The number of files differs, files past the shortest list are not compared
+    FileCount { original: usize, rebuilt: usize },
+    /// A field of a FileInfo entry differs
+    FileInfo {
+        index: usize,
+        path: String,
+        field: &'static str,
+        original: u32,
+        rebuilt: u32,
+    },
+    /// An entry in the filename table differs
+    Filename {
+        index: usize,
+        original: String,
+        rebuilt: String,
+    },
+    /// The data offsets of a file differ
+    FileLocation {
+        index: usize,
+        path: String,
+        original: Vec<FileLoc5>,
+        rebuilt: Vec<FileLoc5>,
+    },
+    /// The data of a file differs, `offset` is the first differing byte relative to the start of the file
+    FileData {
+        index: usize,
+        path: String,
+        location: FileLoc5,
+        offset: u64,
+    },
+    /// The size of a data file differs
+    DataFileSize {
+        filelist_num: u32,
+        original: u64,
+        rebuilt: u64,
+    },
+    /// The serialized header differs at `offset`
+    HeaderBytes { offset: u64 },
+}
+
+impl Display for Divergence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Divergence::Header {
+                field,
+                original,
+                rebuilt,
+            } => write!(f, "Header field {field}: {original} != {rebuilt}"),
+            Divergence::FileCount { original, rebuilt } => {
+                write!(f, "File count: {original} != {rebuilt}")
+            }
+            Divergence::FileInfo {
+                index,
+                path,
+                field,
+                original,
+                rebuilt,
+            } => write!(
+                f,
+                "File {index} ({path}) {field}: 0x{original:x} != 0x{rebuilt:x}"
+            ),
+            Divergence::Filename {
+                index,
+                original,
+                rebuilt,
+            } => write!(f, "Filename {index}: '{original}' != '{rebuilt}'"),
+            Divergence::FileLocation {
+                index,
+                path,
+                original,
+                rebuilt,
+            } => write!(
+                f,
+                "File {index} ({path}) locations: {} != {}",
+                format_locations(original),
+                format_locations(rebuilt)
+            ),
+            Divergence::FileData {
+                index,
+                path,
+                location,
+                offset,
+            } => write!(
+                f,
+                "File {index} ({path}) data in data file {} at 0x{:x} differs at offset 0x{offset:x}",
+                location.filelist_num, location.addr
+            ),
+            Divergence::DataFileSize {
+                filelist_num,
+                original,
+                rebuilt,
+            } => write!(
+                f,
+                "Data file {filelist_num} size: 0x{original:x} != 0x{rebuilt:x}"
+            ),
+            Divergence::HeaderBytes { offset } => {
+                write!(f, "Header data differs at offset 0x{offset:x}")
+            }
+        }
+    }
+}
+
+fn format_locations(locations: &[FileLoc5]) -> String {
+    let locations: Vec<String> = locations
+        .iter()
+        .map(|l| format!("{}:0x{:x}", l.filelist_num, l.addr))
+        .collect();
+
+    format!("[{}]", locations.join(", "))
+}
+
+/// Compares the header fields, FileInfo entries, filenames and data offsets of two filelists
+pub fn diff_filelists(original: &UXFileList, rebuilt: &UXFileList) -> Vec<Divergence> {
+    let mut result = vec![];
+
+    let mut header = |field, original: String, rebuilt: String| {
+        if original != rebuilt {
+            result.push(Divergence::Header {
+                field,
+                original,
+                rebuilt,
+            });
+        }
+    };
+
+    header(
+        "version",
+        original.version.to_string(),
+        rebuilt.version.to_string(),
+    );
+    header(
+        "num_filelists",
+        format!("{:?}", original.num_filelists),
+        format!("{:?}", rebuilt.num_filelists),
+    );
+    header(
+        "build_type",
+        format!("{:?}", original.build_type),
+        format!("{:?}", rebuilt.build_type),
+    );
+    header(
+        "endian",
+        endian_name(original.endian).to_string(),
+        endian_name(rebuilt.endian).to_string(),
+    );
+
+    if original.files.len() != rebuilt.files.len() {
+        result.push(Divergence::FileCount {
+            original: original.files.len(),
+            rebuilt: rebuilt.files.len(),
+        });
+    }
+
+    for (index, ((opath, oinfo), (rpath, rinfo))) in
+        original.files.iter().zip(rebuilt.files.iter()).enumerate()
+    {
+        if opath != rpath {
+            result.push(Divergence::Filename {
+                index,
+                original: opath.clone(),
+                rebuilt: rpath.clone(),
+            });
+        }
+
+        for (field, o, r) in [
+            ("length", oinfo.length, rinfo.length),
+            ("hashcode", oinfo.hashcode, rinfo.hashcode),
+            ("version", oinfo.version, rinfo.version),
+            ("flags", oinfo.flags, rinfo.flags),
+            ("unk0", oinfo.unk0, rinfo.unk0),
+        ] {
+            if o != r {
+                result.push(Divergence::FileInfo {
+                    index,
+                    path: opath.clone(),
+                    field,
+                    original: o,
+                    rebuilt: r,
+                });
+            }
+        }
+
+        if oinfo.fileloc != rinfo.fileloc {
+            result.push(Divergence::FileLocation {
+                index,
+                path: opath.clone(),
+                original: oinfo.fileloc.clone(),
+                rebuilt: rinfo.fileloc.clone(),
+            });
+        }
+    }
+
+    result
+}
+
+/// Compares the data of a rebuilt file against the original data files at each of its locations
+pub fn diff_file_data(
+    vfs: &mut FilelistVfs,
+    index: usize,
+    path: &str,
+    fileloc: &[FileLoc5],
+    data: &[u8],
+) -> Result<Vec<Divergence>> {
+    let mut result = vec![];
+    for loc in fileloc {
+        let original_data = vfs.read_raw(loc.filelist_num, loc.addr as u64, data.len())?;
+        if let Some(offset) = first_difference(&original_data, data) {
+            result.push(Divergence::FileData {
+                index,
+                path: path.to_string(),
+                location: loc.clone(),
+                offset,
+            });
+        }
+    }
+
+    Ok(result)
+}
+
+/// Compares the sizes of the original data files against the rebuilt ones
+///
+/// Original data files include any trailing padding, so `rebuilt` should be padded the same way, see [`crate::builder::FilelistBuilder::padded_data_file_sizes`]
+pub fn diff_data_file_sizes(original: &[u64], rebuilt: &[u64]) -> Vec<Divergence> {
+    (0..original.len().max(rebuilt.len()))
+        .filter_map(|i| {
+            let original = original.get(i).copied().unwrap_or(0);
+            let rebuilt = rebuilt.get(i).copied().unwrap_or(0);
+            (original != rebuilt).then_some(Divergence::DataFileSize {
+                filelist_num: i as u32,
+                original,
+                rebuilt,
+            })
+        })
+        .collect()
+}
+
+/// Serializes `rebuilt` and compares it against the original header data
+pub fn diff_header_bytes(original: &[u8], rebuilt: &UXFileList) -> Result<Option<Divergence>> {
+    let mut rebuilt_header = Cursor::new(vec![]);
+    rebuilt.write(&mut rebuilt_header)?;
+
+    Ok(first_difference(original, rebuilt_header.get_ref())
+        .map(|offset| Divergence::HeaderBytes { offset }))
+}
+
+/// Returns the offset of the first differing byte, including a difference in length
+pub fn first_difference(a: &[u8], b: &[u8]) -> Option<u64> {
+    a.iter()
+        .zip(b.iter())
+        .position(|(a, b)| a != b)
+        .or((a.len() != b.len()).then_some(a.len().min(b.len())))
+        .map(|o| o as u64)
+}
+
+fn endian_name(endian: Endian) -> &'static str {
+    match endian {
+        Endian::Big => "big",
+        Endian::Little => "little",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::unified::UXFileInfo;
+
+    fn test_filelist() -> UXFileList {
+        UXFileList {
+            version: 7,
+            num_filelists: Some(1),
+            build_type: Some(1),
+            endian: Endian::Little,
+            files: (0..3)
+                .map(|i| {
+                    (
+                        format!("x:\\game\\file{i}.edb"),
+                        UXFileInfo {
+                            fileloc: vec![FileLoc5 {
+                                addr: i * 0x800,
+                                filelist_num: 0,
+                            }],
+                            length: 0x100,
+                            hashcode: 0x81000000 | i,
+                            version: 0,
+                            flags: 0,
+                            unk0: 0,
+                            fileloc_unk0: vec![],
+                        },
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn identical_filelists() {
+        assert!(diff_filelists(&test_filelist(), &test_filelist()).is_empty());
+    }
+
+    #[test]
+    fn structural_divergences() {
+        let original = test_filelist();
+        let mut rebuilt = test_filelist();
+        rebuilt.build_type = Some(2);
+        rebuilt.files[0].0 = "x:\\game\\other.edb".to_string();
+        rebuilt.files[1].1.hashcode = 0x81001234;
+        rebuilt.files[2].1.fileloc[0].addr = 0x2000;
+        rebuilt.files.pop();
+
+        assert_eq!(
+            diff_filelists(&original, &rebuilt),
+            [
+                Divergence::Header {
+                    field: "build_type",
+                    original: "Some(1)".to_string(),
+                    rebuilt: "Some(2)".to_string(),
+                },
+                Divergence::FileCount {
+                    original: 3,
+                    rebuilt: 2,
+                },
+                Divergence::Filename {
+                    index: 0,
+                    original: "x:\\game\\file0.edb".to_string(),
+                    rebuilt: "x:\\game\\other.edb".to_string(),
+                },
+                Divergence::FileInfo {
+                    index: 1,
+                    path: "x:\\game\\file1.edb".to_string(),
+                    field: "hashcode",
+                    original: 0x81000001,
+                    rebuilt: 0x81001234,
+                },
+            ]
+        );
+
+        rebuilt = test_filelist();
+        rebuilt.files[2].1.fileloc[0].addr = 0x2000;
+        assert_eq!(
+            diff_filelists(&original, &rebuilt),
+            [Divergence::FileLocation {
+                index: 2,
+                path: "x:\\game\\file2.edb".to_string(),
+                original: original.files[2].1.fileloc.clone(),
+                rebuilt: rebuilt.files[2].1.fileloc.clone(),
+            }]
+        );
+    }
+
+    #[test]
+    fn header_bytes() {
+        let filelist = test_filelist();
+        let mut original = Cursor::new(vec![]);
+        filelist.write(&mut original).unwrap();
+        let mut original = original.into_inner();
+
+        assert_eq!(diff_header_bytes(&original, &filelist).unwrap(), None);
+
+        original[0x20] ^= 0xff;
+        assert_eq!(
+            diff_header_bytes(&original, &filelist).unwrap(),
+            Some(Divergence::HeaderBytes { offset: 0x20 })
+        );
+    }
+
+    #[test]
+    fn data_file_sizes() {
+        assert!(diff_data_file_sizes(&[0x1000, 0x800], &[0x1000, 0x800]).is_empty());
+        assert_eq!(
+            diff_data_file_sizes(&[0x1000, 0x800], &[0x1000]),
+            [Divergence::DataFileSize {
+                filelist_num: 1,
+                original: 0x800,
+                rebuilt: 0,
+            }]
+        );
+    }
+
+    #[test]
+    fn first_difference_offsets() {
+        assert_eq!(first_difference(b"abcd", b"abcd"), None);
+        assert_eq!(first_difference(b"abcd", b"abxd"), Some(2));
+        assert_eq!(first_difference(b"abcd", b"ab"), Some(2));
+    }
+}
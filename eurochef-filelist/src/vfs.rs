@@ -0,0 +1,557 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufReader, Cursor, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+
+use binrw::BinReaderExt;
+
+use crate::{
+    error::{FilelistError, Result},
+    filter::FileFilter,
+    structures::FileLoc5,
+    unified::UXFileList,
+};
+
+/// Magic of EngineX geo files ('GEOM')
+const GEOM_MAGIC: u32 = 0x47454F4D;
+
+pub trait DataFile: Read + Seek {}
+impl<R: Read + Seek> DataFile for R {}
+
+/// Read-only view of the files contained in a filelist and its data files
+pub struct FilelistVfs {
+    filelist: UXFileList,
+    data_files: Vec<Box<dyn DataFile>>,
+
+    /// Normalized path -> index into `filelist.files`
+    path_index: HashMap<String, usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct VfsMetadata {
+    pub is_dir: bool,
+
+    /// Size of the file as it would be extracted to disk. Always 0 for directories
+    pub length: u32,
+    pub hashcode: u32,
+    pub version: u32,
+    pub flags: u32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct VfsDirEntry {
+    pub name: String,
+    pub is_dir: bool,
+}
+
+impl FilelistVfs {
+    pub fn new(filelist: UXFileList, data_files: Vec<Box<dyn DataFile>>) -> Result<Self> {
+        let expected_data_files = filelist.num_filelists.map(|n| n as usize + 1).unwrap_or(1);
+        if data_files.len() < expected_data_files {
+            return Err(FilelistError::MissingDataFile(format!(
+                "#{}",
+                data_files.len()
+            )));
+        }
+
+        let path_index = filelist
+            .files
+            .iter()
+            .enumerate()
+            .filter(|(_, (path, _))| !path.is_empty())
+            .map(|(i, (path, _))| (normalize_path(path), i))
+            .collect();
+
+        Ok(Self {
+            filelist,
+            data_files,
+            path_index,
+        })
+    }
+
+    /// Opens a filelist header (eg. `Filelist.bin`) along with the data files next to it
+    pub fn open_filelist<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let mut reader = BufReader::new(File::open(path)?);
+        let filelist = UXFileList::read(&mut reader)?;
+
+        Self::open_data_files(path, filelist)
+    }
+
+    /// Opens the data files next to the filelist header at `path` for a filelist that has already been read
+    pub fn open_data_files<P: AsRef<Path>>(path: P, filelist: UXFileList) -> Result<Self> {
+        let path = path.as_ref();
+        let mut data_files: Vec<Box<dyn DataFile>> = vec![];
+        if let Some(num_filelists) = filelist.num_filelists {
+            for i in 0..(num_filelists as u32 + 1) {
+                let data_path = data_file_path(path, Some(i));
+                data_files.push(Box::new(BufReader::new(open_data_file(&data_path)?)));
+            }
+        } else {
+            let data_path = data_file_path(path, None);
+            data_files.push(Box::new(BufReader::new(open_data_file(&data_path)?)));
+        }
+
+        Self::new(filelist, data_files)
+    }
+
+    pub fn filelist(&self) -> &UXFileList {
+        &self.filelist
+    }
+
+    /// Returns the index of the file at the given path
+    pub fn find(&self, path: &str) -> Option<usize> {
+        self.path_index.get(&normalize_path(path)).cloned()
+    }
+
+    /// Returns the index of the first file with the given hashcode
+    pub fn find_by_hashcode(&self, hashcode: u32) -> Option<usize> {
+        self.filelist
+            .files
+            .iter()
+            .position(|(_, info)| info.hashcode == hashcode)
+    }
+
+    /// Opens a file by its path. Paths are case-insensitive, and both '/' and '\\' are accepted as separators. The drive letter is optional.
+    pub fn open(&mut self, path: &str) -> Result<Cursor<Vec<u8>>> {
+        let index = self
+            .find(path)
+            .ok_or_else(|| FilelistError::FileNotFound(path.to_string()))?;
+
+        Ok(Cursor::new(self.read_file(index)?))
+    }
+
+    pub fn open_by_hashcode(&mut self, hashcode: u32) -> Result<Cursor<Vec<u8>>> {
+        let index = self
+            .find_by_hashcode(hashcode)
+            .ok_or_else(|| FilelistError::FileNotFound(format!("{hashcode:x}")))?;
+
+        Ok(Cursor::new(self.read_file(index)?))
+    }
+
+    /// Reads the data of the file at the given index in the filelist
+    pub fn read_file(&mut self, index: usize) -> Result<Vec<u8>> {
+        self.read_file_location(index, 0)
+    }
+
+    /// Reads the data of a specific copy of the file at the given index in the filelist
+    pub fn read_file_location(&mut self, index: usize, location: usize) -> Result<Vec<u8>> {
+        let length = self.file_length(index)?;
+        let loc = self.location(index, location)?;
+        let df = self.data_file(loc.filelist_num)?;
+
+        df.seek(SeekFrom::Start(loc.addr as u64))?;
+        let mut data = vec![0u8; length as usize];
+        df.read_exact(&mut data)?;
+
+        Ok(data)
+    }
+
+    /// Compares every duplicate copy of the file at the given index against `data`, the data of its first copy
+    ///
+    /// Returns the location indices of the copies that don't match
+    pub fn mismatched_copies(&mut self, index: usize, data: &[u8]) -> Result<Vec<usize>> {
+        let mut mismatches = vec![];
+        for location in 1..self.filelist.files[index].1.fileloc.len() {
+            if self.read_file_location(index, location)? != data {
+                mismatches.push(location);
+            }
+        }
+
+        Ok(mismatches)
+    }
+
+    /// Reads up to `length` bytes from a data file, ignoring the filelist. Returns less data when the end of the data file is reached
+    pub fn read_raw(&mut self, filelist_num: u32, addr: u64, length: usize) -> Result<Vec<u8>> {
+        let df = self.data_file(filelist_num)?;
+        df.seek(SeekFrom::Start(addr))?;
+
+        let mut data = Vec::with_capacity(length);
+        df.take(length as u64).read_to_end(&mut data)?;
+
+        Ok(data)
+    }
+
+    /// Returns the real size of the file at the given index.
+    ///
+    /// Filelists store the base size of geo files, which excludes any data that is loaded afterwards, so the full size is read from the geo header instead.
+    pub fn file_length(&mut self, index: usize) -> Result<u32> {
+        let endian = self.filelist.endian;
+        let length = self.filelist.files[index].1.length;
+        if self.is_geo(index)? {
+            let loc = self.location(index, 0)?;
+            let df = self.data_file(loc.filelist_num)?;
+            df.seek(SeekFrom::Start(loc.addr as u64 + 0x14))?;
+            Ok(df.read_type(endian)?)
+        } else {
+            Ok(length)
+        }
+    }
+
+    /// Checks whether the file at the given index is a geo file by its magic
+    pub fn is_geo(&mut self, index: usize) -> Result<bool> {
+        let endian = self.filelist.endian;
+        if self.filelist.files[index].1.length < 0x18 {
+            return Ok(false);
+        }
+
+        let loc = self.location(index, 0)?;
+        let df = self.data_file(loc.filelist_num)?;
+        df.seek(SeekFrom::Start(loc.addr as u64))?;
+        let magic: u32 = df.read_type(endian)?;
+
+        Ok(magic == GEOM_MAGIC)
+    }
+
+    /// Returns the indices of all files matching the filter
+    pub fn filter_files(&mut self, filter: &FileFilter) -> Result<Vec<usize>> {
+        let mut result = vec![];
+        for i in 0..self.filelist.files.len() {
+            if filter.matches(self, i)? {
+                result.push(i);
+            }
+        }
+
+        Ok(result)
+    }
+
+    pub fn metadata(&mut self, path: &str) -> Result<VfsMetadata> {
+        if let Some(index) = self.find(path) {
+            let length = self.file_length(index)?;
+            let info = &self.filelist.files[index].1;
+            return Ok(VfsMetadata {
+                is_dir: false,
+                length,
+                hashcode: info.hashcode,
+                version: info.version,
+                flags: info.flags,
+            });
+        }
+
+        if self.is_dir(path) {
+            Ok(VfsMetadata {
+                is_dir: true,
+                length: 0,
+                hashcode: 0,
+                version: 0,
+                flags: 0,
+            })
+        } else {
+            Err(FilelistError::FileNotFound(path.to_string()))
+        }
+    }
+
+    /// Lists the direct children of a directory. An empty path lists the root of the filelist
+    pub fn list_dir(&self, path: &str) -> Result<Vec<VfsDirEntry>> {
+        let dir = normalize_path(path);
+        if !dir.is_empty() && !self.is_dir(&dir) {
+            return Err(FilelistError::FileNotFound(path.to_string()));
+        }
+
+        let mut entries: Vec<VfsDirEntry> = vec![];
+        for (file_path, _) in self.filelist.files.iter() {
+            let file_path = strip_drive(file_path);
+            let normalized = normalize_path(file_path);
+            let relative = if dir.is_empty() {
+                normalized.as_str()
+            } else if let Some(r) = normalized
+                .strip_prefix(&dir)
+                .and_then(|r| r.strip_prefix('\\'))
+            {
+                r
+            } else {
+                continue;
+            };
+
+            // Keep the original casing for the entry name
+            let name_start = file_path.len() - relative.len();
+            let (name, is_dir) = match relative.find('\\') {
+                Some(end) => (&file_path[name_start..name_start + end], true),
+                None => (&file_path[name_start..], false),
+            };
+
+            if name.is_empty()
+                || entries
+                    .iter()
+                    .any(|e| e.is_dir == is_dir && e.name.eq_ignore_ascii_case(name))
+            {
+                continue;
+            }
+
+            entries.push(VfsDirEntry {
+                name: name.to_string(),
+                is_dir,
+            });
+        }
+
+        Ok(entries)
+    }
+
+    fn is_dir(&self, path: &str) -> bool {
+        let dir = normalize_path(path);
+        dir.is_empty()
+            || self.path_index.keys().any(|p| {
+                p.strip_prefix(&dir)
+                    .map(|r| r.starts_with('\\'))
+                    .unwrap_or(false)
+            })
+    }
+
+    fn location(&self, index: usize, location: usize) -> Result<FileLoc5> {
+        let (filename, info) = &self.filelist.files[index];
+        info.fileloc
+            .get(location)
+            .cloned()
+            .ok_or_else(|| FilelistError::FileNotFound(format!("{filename} (copy {location})")))
+    }
+
+    /// Returns the size of every data file, including any trailing padding
+    pub fn data_file_sizes(&mut self) -> Result<Vec<u64>> {
+        let mut sizes = vec![];
+        for f in self.data_files.iter_mut() {
+            sizes.push(f.seek(SeekFrom::End(0))?);
+        }
+
+        Ok(sizes)
+    }
+
+    fn data_file(&mut self, filelist_num: u32) -> Result<&mut Box<dyn DataFile>> {
+        let num = filelist_num as usize;
+        self.data_files
+            .get_mut(num)
+            .ok_or_else(|| FilelistError::MissingDataFile(format!("#{num}")))
+    }
+}
+
+/// Returns the path of a data file belonging to the filelist header at `path`. `None` refers to the single '.DAT' file
+pub fn data_file_path(path: &Path, filelist_num: Option<u32>) -> PathBuf {
+    match filelist_num {
+        Some(i) => path.with_extension(format!("{:03}", i)),
+        None => path.with_extension("DAT"),
+    }
+}
+
+fn open_data_file(path: &Path) -> Result<File> {
+    File::open(path).map_err(|e| {
+        if e.kind() == std::io::ErrorKind::NotFound {
+            FilelistError::MissingDataFile(path.to_string_lossy().to_string())
+        } else {
+            e.into()
+        }
+    })
+}
+
+fn strip_drive(path: &str) -> &str {
+    let path = match path.as_bytes() {
+        [d, b':', ..] if d.is_ascii_alphabetic() => &path[2..],
+        _ => path,
+    };
+
+    path.trim_start_matches(['\\', '/'])
+}
+
+/// Converts a path to the form used for lookups (lowercase, backslash separated, no drive letter)
+pub fn normalize_path(path: &str) -> String {
+    strip_drive(path)
+        .trim_end_matches(['\\', '/'])
+        .replace('/', "\\")
+        .to_ascii_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use binrw::{BinWriterExt, Endian};
+
+    use super::*;
+    use crate::unified::UXFileInfo;
+
+    /// Creates a single data file filelist with two text files and a geo file with 0x20 bytes of streamed data
+    ///
+    /// The first file has an identical copy at 0x1800 and a corrupted one at 0x1880
+    fn test_vfs() -> FilelistVfs {
+        let mut data = Cursor::new(vec![]);
+        let mut files = vec![];
+        for (i, path) in [
+            "x:\\Game\\Data\\file0.txt",
+            "x:\\Game\\Data\\Sub\\file1.txt",
+            "x:\\Game\\Binary\\test.edb",
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let addr = i as u32 * 0x800;
+            data.seek(SeekFrom::Start(addr as u64)).unwrap();
+            let length = if path.ends_with(".edb") {
+                data.write_le(&GEOM_MAGIC).unwrap();
+                data.write_le(&[0u32; 4]).unwrap();
+                // Full size, base size
+                data.write_le(&0x40u32).unwrap();
+                data.write_le(&0x20u32).unwrap();
+                data.write_all(&[0xee; 0x24]).unwrap();
+                0x20
+            } else {
+                data.write_all(&[i as u8; 0x10]).unwrap();
+                0x10
+            };
+
+            files.push((
+                path.to_string(),
+                UXFileInfo {
+                    fileloc: vec![FileLoc5 {
+                        addr,
+                        filelist_num: 0,
+                    }],
+                    length,
+                    hashcode: 0x81000000 | i as u32,
+                    version: 0,
+                    flags: 0,
+                    unk0: 0,
+                    fileloc_unk0: vec![],
+                },
+            ));
+        }
+
+        data.seek(SeekFrom::Start(0x1800)).unwrap();
+        data.write_all(&[0u8; 0x10]).unwrap();
+        data.seek(SeekFrom::Start(0x1880)).unwrap();
+        data.write_all(&[0u8; 0xf]).unwrap();
+        data.write_all(&[0xff]).unwrap();
+        for addr in [0x1800, 0x1880] {
+            files[0].1.fileloc.push(FileLoc5 {
+                addr,
+                filelist_num: 0,
+            });
+        }
+
+        let filelist = UXFileList {
+            version: 5,
+            num_filelists: Some(0),
+            build_type: None,
+            endian: Endian::Little,
+            files,
+        };
+
+        FilelistVfs::new(filelist, vec![Box::new(data)]).unwrap()
+    }
+
+    fn entry(name: &str, is_dir: bool) -> VfsDirEntry {
+        VfsDirEntry {
+            name: name.to_string(),
+            is_dir,
+        }
+    }
+
+    #[test]
+    fn list_dir() {
+        let vfs = test_vfs();
+        assert_eq!(vfs.list_dir("").unwrap(), [entry("Game", true)]);
+        assert_eq!(
+            vfs.list_dir("x:\\game").unwrap(),
+            [entry("Data", true), entry("Binary", true)]
+        );
+        assert_eq!(
+            vfs.list_dir("GAME/data/").unwrap(),
+            [entry("file0.txt", false), entry("Sub", true)]
+        );
+        assert!(matches!(
+            vfs.list_dir("game/missing"),
+            Err(FilelistError::FileNotFound(_))
+        ));
+        assert!(matches!(
+            vfs.list_dir("game/data/file0.txt"),
+            Err(FilelistError::FileNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn metadata() {
+        let mut vfs = test_vfs();
+        for dir in ["x:\\game\\data", "Game/Data/Sub", "/game"] {
+            let metadata = vfs.metadata(dir).unwrap();
+            assert!(metadata.is_dir);
+            assert_eq!(metadata.length, 0);
+        }
+
+        let metadata = vfs.metadata("game/data/sub/FILE1.TXT").unwrap();
+        assert!(!metadata.is_dir);
+        assert_eq!(metadata.length, 0x10);
+        assert_eq!(metadata.hashcode, 0x81000001);
+
+        assert!(matches!(
+            vfs.metadata("game/dat"),
+            Err(FilelistError::FileNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn open_paths() {
+        let mut vfs = test_vfs();
+        for path in [
+            "x:\\Game\\Data\\file0.txt",
+            "X:\\GAME\\DATA\\FILE0.TXT",
+            "\\game\\data\\file0.txt",
+            "game/data/file0.txt",
+        ] {
+            assert_eq!(vfs.open(path).unwrap().into_inner(), [0u8; 0x10]);
+        }
+
+        assert_eq!(
+            vfs.open("c:\\game\\data\\sub\\file1.txt")
+                .unwrap()
+                .into_inner(),
+            [1u8; 0x10]
+        );
+        assert_eq!(
+            vfs.open_by_hashcode(0x81000001).unwrap().into_inner(),
+            [1u8; 0x10]
+        );
+
+        assert!(matches!(
+            vfs.open("x:\\game\\data\\file2.txt"),
+            Err(FilelistError::FileNotFound(_))
+        ));
+        assert!(matches!(
+            vfs.open_by_hashcode(0x81001234),
+            Err(FilelistError::FileNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn geo_file_length() {
+        let mut vfs = test_vfs();
+        let index = vfs.find("game/binary/test.edb").unwrap();
+
+        // The filelist stores the base size, the full size comes from the geo header
+        assert_eq!(vfs.filelist().files[index].1.length, 0x20);
+        assert!(vfs.is_geo(index).unwrap());
+        assert_eq!(vfs.file_length(index).unwrap(), 0x40);
+        assert_eq!(vfs.metadata("game/binary/test.edb").unwrap().length, 0x40);
+        assert_eq!(
+            vfs.open_by_hashcode(0x81000002).unwrap().get_ref().len(),
+            0x40
+        );
+
+        assert!(!vfs.is_geo(0).unwrap());
+        assert_eq!(vfs.file_length(0).unwrap(), 0x10);
+    }
+
+    #[test]
+    fn duplicate_copies() {
+        let mut vfs = test_vfs();
+        let data = vfs.read_file(0).unwrap();
+        assert_eq!(vfs.read_file_location(0, 1).unwrap(), data);
+        assert_eq!(vfs.mismatched_copies(0, &data).unwrap(), [2]);
+
+        let data = vfs.read_file(1).unwrap();
+        assert!(vfs.mismatched_copies(1, &data).unwrap().is_empty());
+
+        assert!(matches!(
+            vfs.read_file_location(0, 3),
+            Err(FilelistError::FileNotFound(_))
+        ));
+    }
+}
@@ -11,7 +11,9 @@ use eurochef_edb::{
     anim::EXGeoBaseAnimSkin, binrw::BinReaderExt, edb::EdbFile, entity::EXGeoEntity,
     versions::Platform,
 };
-use eurochef_shared::{entities::read_entity, textures::UXGeoTexture};
+use eurochef_shared::{
+    entities::read_entity, filesystem::path::DissectedFilelistPath, textures::UXGeoTexture,
+};
 use image::ImageOutputFormat;
 use indicatif::{ProgressBar, ProgressIterator, ProgressStyle};
 
@@ -47,7 +49,8 @@ pub fn execute_command(
 
     let file = File::open(&filename)?;
     let reader = BufReader::new(file);
-    let mut edb = EdbFile::new(Box::new(reader), platform)?;
+    let game = DissectedFilelistPath::dissect(&filename).map(|d| d.game);
+    let mut edb = EdbFile::with_game(Box::new(reader), platform, game.as_deref())?;
     let header = edb.header.clone();
 
     if header.animskin_list.len() == 0 {
@@ -149,7 +152,9 @@ pub fn execute_command(
 
             edb.seek(std::io::SeekFrom::Start(e.common.address as u64))?;
 
-            let ent = edb.read_type_args::<EXGeoEntity>(edb.endian, (header.version, platform));
+            let ent = edb.collect_references(|edb| {
+                edb.read_type_args::<EXGeoEntity>(edb.endian, (header.version, platform))
+            });
 
             if let Err(err) = ent {
                 error!("Failed to read entity: {err}");
@@ -8,7 +8,9 @@ use std::{
 use anyhow::Context;
 use base64::Engine;
 use eurochef_edb::{binrw::BinReaderExt, edb::EdbFile, entity::EXGeoEntity, versions::Platform};
-use eurochef_shared::{entities::read_entity, textures::UXGeoTexture};
+use eurochef_shared::{
+    entities::read_entity, filesystem::path::DissectedFilelistPath, textures::UXGeoTexture,
+};
 use image::ImageOutputFormat;
 use indicatif::{ProgressBar, ProgressIterator, ProgressStyle};
 
@@ -49,7 +51,8 @@ pub fn execute_command(
 
     let file = File::open(&filename)?;
     let reader = BufReader::new(file);
-    let mut edb = EdbFile::new(Box::new(reader), platform)?;
+    let game = DissectedFilelistPath::dissect(&filename).map(|d| d.game);
+    let mut edb = EdbFile::with_game(Box::new(reader), platform, game.as_deref())?;
     let header = edb.header.clone();
 
     match platform {
@@ -147,12 +150,14 @@ pub fn execute_command(
         .collect();
 
     // Find entities in refpointers
-    for (i, r) in header.refpointer_list.iter().enumerate() {
-        edb.seek(std::io::SeekFrom::Start(r.address as u64))?;
-        let etype = edb.read_type::<u32>(edb.endian)?;
-
-        if etype == 0x601 || etype == 0x603 {
-            entity_offsets.push((r.address as u64, format!("ref_{i}")))
+    for i in 0..header.refpointer_list.len() {
+        match edb.refpointer(i) {
+            Ok(r) => {
+                if r.type_code == 0x601 || r.type_code == 0x603 {
+                    entity_offsets.push((r.address as u64, format!("ref_{}", r.index)))
+                }
+            }
+            Err(err) => warn!("Skipping refpointer: {err}"),
         }
     }
 
@@ -174,7 +179,9 @@ pub fn execute_command(
 
         edb.seek(std::io::SeekFrom::Start(*ent_offset))?;
 
-        let ent = edb.read_type_args::<EXGeoEntity>(edb.endian, (header.version, platform));
+        let ent = edb.collect_references(|edb| {
+            edb.read_type_args::<EXGeoEntity>(edb.endian, (header.version, platform))
+        });
         if let Err(err) = ent {
             error!("Failed to read entity: {err}");
             continue;
@@ -0,0 +1,52 @@
+use std::{fs::File, io::BufReader, path::Path};
+
+use anyhow::Context;
+use eurochef_edb::{edb::EdbFile, versions::Platform};
+use eurochef_shared::{
+    filesystem::path::DissectedFilelistPath, geofile::UXGeoFile, hashcodes::parse_hashcodes,
+};
+use nohash_hasher::IntMap;
+
+use crate::PlatformArg;
+
+pub fn execute_command(
+    filename: String,
+    platform: Option<PlatformArg>,
+    output_folder: Option<String>,
+    hashcodes_file: Option<String>,
+) -> anyhow::Result<()> {
+    let output_folder = output_folder.unwrap_or(format!(
+        "./geofiles/{}/",
+        Path::new(&filename).file_name().unwrap().to_string_lossy(),
+    ));
+
+    let platform = platform
+        .map(|p| p.into())
+        .or(Platform::from_path(&filename))
+        .expect("Failed to detect platform");
+
+    let hashcodes = match hashcodes_file {
+        Some(path) => parse_hashcodes(
+            &std::fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read hashcodes file {path}"))?,
+        ),
+        None => IntMap::default(),
+    };
+
+    let file = File::open(&filename)?;
+    let reader = BufReader::new(file);
+    let game = DissectedFilelistPath::dissect(&filename).map(|d| d.game);
+    let mut edb = EdbFile::with_game(Box::new(reader), platform, game.as_deref())?;
+
+    let geofile = UXGeoFile::read(&mut edb, &hashcodes)?;
+    geofile.save(&output_folder)?;
+
+    info!(
+        "Exported {} entities, {} textures and {} maps to {output_folder}",
+        geofile.entities.len(),
+        geofile.textures.len(),
+        geofile.maps.len()
+    );
+
+    Ok(())
+}
@@ -13,7 +13,10 @@ use eurochef_edb::{
     versions::Platform,
 };
 
-use eurochef_shared::maps::{TriggerInformation, UXGeoTrigger};
+use eurochef_shared::{
+    filesystem::path::DissectedFilelistPath,
+    maps::{TriggerInformation, UXGeoTrigger},
+};
 use serde::Serialize;
 
 use crate::PlatformArg;
@@ -47,7 +50,8 @@ pub fn execute_command(
 
     let file = File::open(&filename)?;
     let reader = BufReader::new(file);
-    let mut edb = EdbFile::new(Box::new(reader), platform)?;
+    let game = DissectedFilelistPath::dissect(&filename).map(|d| d.game);
+    let mut edb = EdbFile::with_game(Box::new(reader), platform, game.as_deref())?;
     let header = edb.header.clone();
 
     if header.map_list.len() == 0 {
@@ -71,7 +75,9 @@ pub fn execute_command(
         edb.seek(std::io::SeekFrom::Start(m.address as u64))?;
 
         let map = edb
-            .read_type_args::<EXGeoMap>(edb.endian, (header.version,))
+            .collect_references(|edb| {
+                edb.read_type_args::<EXGeoMap>(edb.endian, (header.version, edb.profile))
+            })
             .context("Failed to read map")?;
 
         let mut export = EurochefMapExport {
@@ -83,11 +89,9 @@ pub fn execute_command(
         };
 
         for z in &map.zones {
-            let entity_offset = header.refpointer_list[z.entity_refptr as usize].address;
-            edb.seek(std::io::SeekFrom::Start(entity_offset as u64))
-                .context("Mapzone refptr pointer to a non-entity object!")?;
-
-            let ent = edb.read_type_args::<EXGeoEntity>(edb.endian, (header.version, platform))?;
+            let ent = edb
+                .read_refpointer_entity(z.entity_refptr as usize)
+                .context("Failed to read mapzone entity")?;
 
             if let EXGeoEntity::MapZone(mapzone) = ent {
                 export.mapzone_entities.push(mapzone);
@@ -2,7 +2,9 @@ const TICK_STRINGS: &'static str = "⠁⠂⠄⡀⢀⠠⠐⠈";
 
 pub mod animations;
 pub mod entities;
+pub mod geofile;
 mod gltf_export;
 pub mod maps;
+pub mod rewrite;
 pub mod spreadsheets;
 pub mod textures;
@@ -0,0 +1,53 @@
+use std::{
+    fs::File,
+    io::{BufReader, Cursor},
+};
+
+use eurochef_edb::{edb::EdbFile, versions::Platform, writer::EdbWriter};
+use eurochef_shared::filesystem::path::DissectedFilelistPath;
+
+use crate::PlatformArg;
+
+/// Reads a file and writes it back through [`EdbWriter`], checking whether the output is identical to the input
+pub fn execute_command(
+    filename: String,
+    platform: Option<PlatformArg>,
+    output_file: Option<String>,
+) -> anyhow::Result<()> {
+    let platform = platform
+        .map(|p| p.into())
+        .or(Platform::from_path(&filename))
+        .expect("Failed to detect platform");
+
+    let file = File::open(&filename)?;
+    let reader = BufReader::new(file);
+    let game = DissectedFilelistPath::dissect(&filename).map(|d| d.game);
+    let mut edb = EdbFile::with_game(Box::new(reader), platform, game.as_deref())?;
+
+    let writer = EdbWriter::from_edb(&mut edb)?;
+    let mut output = Cursor::new(vec![]);
+    writer.write(&mut output)?;
+    let output = output.into_inner();
+
+    let original = std::fs::read(&filename)?;
+    if let Some(offset) = original.iter().zip(&output).position(|(a, b)| a != b) {
+        warn!("Rewritten file differs from {filename} at offset 0x{offset:x}");
+    } else if original.len() != output.len() {
+        warn!(
+            "Rewritten file is 0x{:x} bytes, original is 0x{:x} bytes",
+            output.len(),
+            original.len()
+        );
+    } else {
+        info!(
+            "Rewritten file is identical to {filename} ({} objects)",
+            writer.objects.len()
+        );
+    }
+
+    if let Some(output_file) = output_file {
+        std::fs::write(output_file, output)?;
+    }
+
+    Ok(())
+}
@@ -23,7 +23,8 @@ pub fn execute_command(filename: String, output_folder: Option<String>) -> anyho
 
     let file = File::open(&filename)?;
     let reader = BufReader::new(file);
-    let mut edb = EdbFile::new(Box::new(reader), Platform::Pc)?;
+    let game = DissectedFilelistPath::dissect(&filename).map(|d| d.game);
+    let mut edb = EdbFile::with_game(Box::new(reader), Platform::Pc, game.as_deref())?;
 
     let (spreadsheet_definitions, hashcodes) =
         if let Some(dissected_path) = DissectedFilelistPath::dissect(&filename) {
@@ -6,7 +6,7 @@ use std::{
 
 use anyhow::Context;
 use eurochef_edb::{edb::EdbFile, versions::Platform};
-use eurochef_shared::textures::UXGeoTexture;
+use eurochef_shared::{filesystem::path::DissectedFilelistPath, textures::UXGeoTexture};
 use indicatif::{ProgressBar, ProgressIterator, ProgressStyle};
 
 use crate::{edb::TICK_STRINGS, PlatformArg};
@@ -36,7 +36,8 @@ pub fn execute_command(
 
     let file = File::open(&filename)?;
     let reader = BufReader::new(file);
-    let mut edb = EdbFile::new(Box::new(reader), platform)?;
+    let game = DissectedFilelistPath::dissect(&filename).map(|d| d.game);
+    let mut edb = EdbFile::with_game(Box::new(reader), platform, game.as_deref())?;
     let header = edb.header.clone();
 
     let pb = ProgressBar::new(header.texture_list.len() as u64)
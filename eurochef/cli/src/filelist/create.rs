@@ -1,46 +1,38 @@
 use anyhow::Context;
-use eurochef_edb::binrw::{BinReaderExt, BinWriterExt};
-use eurochef_edb::versions::{transform_windows_path, Platform};
+use eurochef_edb::versions::Platform;
 use eurochef_filelist::{
-    path,
-    structures::{EXFileListHeader5, FileInfo5, FileLoc5},
+    builder::FilelistBuilder,
+    scr::{ScrFile, ScrFileOptions},
+    vfs::{data_file_path, normalize_path},
 };
 use indicatif::{ProgressBar, ProgressIterator, ProgressStyle};
 use std::{
+    collections::HashMap,
     fs::File,
     io::{Read, Seek, Write},
-    path::Path,
+    path::{Path, PathBuf},
 };
 use walkdir::WalkDir;
 
 use crate::filelist::TICK_STRINGS;
 use crate::PlatformArg;
 
+/// A file to be packed: virtual path, path on disk (if the file exists) and packing options
+pub type PackEntry = (String, Option<String>, ScrFileOptions);
+
 pub fn execute_command(
     input_folder: String,
     output_file: String,
     drive_letter: char,
-    version: u32,
+    version: Option<u32>,
     platform: PlatformArg,
-    split_size: u32,
+    split_size: Option<u32>,
     scr_file: Option<String>,
 ) -> anyhow::Result<()> {
-    // TODO: Make a trait for filelists bundling both the read and from/into functions so that they can be used genericly
     let platform: Platform = platform.into();
-    let endian = platform.endianness();
-
-    if !(5..=7).contains(&version) {
-        panic!("Only version 5, 6 and 7 are supported for packing right now")
-    }
 
     println!("Packing files from {input_folder} with drive letter {drive_letter}:");
 
-    let fp_data = format!("{output_file}.000");
-    let fp_info = format!("{output_file}.bin");
-    let mut f_data = File::create(fp_data).context("Failed to create output file")?;
-
-    let mut files: Vec<(String, FileInfo5)> = vec![];
-
     // TODO: Handle absolute paths on unix
     #[cfg(not(target_os = "windows"))]
     {
@@ -50,249 +42,196 @@ pub fn execute_command(
         }
     }
 
-    let mut file_paths = vec![];
-
-    if let Some(scr_file) = scr_file {
-        println!("Reading files in SCR order");
-        let scr_files = parse_scr_filelist(scr_file).context("Failed to read SCR file")?;
-        let mut file_paths_temp = vec![];
-        for s in scr_files {
-            if &s[1..=2] != ":\\" {
-                panic!("Invalid path in scr file: {s}");
-            }
+    let scr = if let Some(scr_file) = scr_file {
+        Some(
+            ScrFile::read(&mut File::open(scr_file).context("Failed to open SCR file")?)
+                .context("Failed to read SCR file")?,
+        )
+    } else {
+        None
+    };
 
-            file_paths_temp.push(s)
+    let version = match (version, scr.as_ref().and_then(|s| s.info.version)) {
+        (Some(version), Some(scr_version)) if version != scr_version => {
+            anyhow::bail!(
+                "Requested filelist version {version} does not match version {scr_version} from the SCR file"
+            );
         }
-        println!("Loaded {} paths from SCR", file_paths_temp.len());
-        let pb = ProgressBar::new(0);
-        pb.set_style(
-            ProgressStyle::with_template("{spinner:.green} [{elapsed_precise}] {msg}")
-                .unwrap()
-                .progress_chars("##-")
-                .tick_chars(&TICK_STRINGS),
-        );
-        pb.set_message("Locating files");
-
-        for p in &file_paths_temp {
-            // Join path to root folder and change globbing pattern to be `glob`-compatible
-            let path_on_disk =
-                Path::new(&input_folder).join(transform_windows_path(&p[3..].replace("#", "?")));
-
-            let mut found_files = false;
-            #[allow(for_loops_over_fallibles)]
-            for entry in glob::glob(&path_on_disk.to_string_lossy())
-                .context("Failed to parse SCR glob pattern, report to cohae")?
-            {
-                let entry = entry?;
-                if std::fs::metadata(&entry)?.is_file() {
-                    let fpath = pathdiff::diff_paths(&entry, &input_folder)
-                        .unwrap()
-                        .to_string_lossy()
-                        .replace('/', "\\");
-
-                    file_paths.push((
-                        format!("{drive_letter}:\\{fpath}"),
-                        entry.to_string_lossy().to_string(),
-                    ))
-                }
-
-                found_files = true;
-            }
+        (version, scr_version) => version.or(scr_version).unwrap_or(7),
+    };
 
-            if !found_files {
-                // TODO: log crate when?
-                println!("Warning: SCR path {p} yielded no results")
-            }
-        }
-    } else {
-        let pb = ProgressBar::new(0);
-        pb.set_style(
-            ProgressStyle::with_template("{spinner:.green} [{elapsed_precise}] {msg}")
-                .unwrap()
-                .progress_chars("##-")
-                .tick_chars(&TICK_STRINGS),
-        );
-        pb.set_message("Locating files");
-
-        for e in WalkDir::new(&input_folder) {
-            pb.tick();
-            let e = e?;
-            if e.file_type().is_file() {
-                let fpath = pathdiff::diff_paths(e.path(), &input_folder)
-                    .unwrap()
-                    .to_string_lossy()
-                    .replace('/', "\\");
-
-                file_paths.push((
-                    format!("{drive_letter}:\\{fpath}"),
-                    e.path().to_string_lossy().to_string(),
-                ))
-            }
+    if let (Some(split_size), Some(scr_split_size)) =
+        (split_size, scr.as_ref().and_then(|s| s.info.split_size))
+    {
+        if split_size != scr_split_size {
+            anyhow::bail!(
+                "Requested split size 0x{split_size:x} does not match split size 0x{scr_split_size:x} from the SCR file"
+            );
         }
+    }
 
-        pb.finish_and_clear();
-        println!("Located {} files", file_paths.len());
+    let mut builder = FilelistBuilder::new(version, platform.endianness())?;
+    if let Some(split_size) = split_size {
+        builder.split_size = split_size as u64;
     }
+    if let Some(scr) = &scr {
+        builder.apply_scr_info(&scr.info)?;
+    }
+
+    let entries = collect_files(&input_folder, drive_letter, scr.as_ref())?;
+    let min_data_file_sizes = scr.map(|s| s.info.data_file_sizes).unwrap_or_default();
 
-    let mut filelist_num = 0;
+    let fp_info = PathBuf::from(format!("{output_file}.bin"));
 
     let pb =
-        ProgressBar::new(file_paths.len() as u64).with_finish(indicatif::ProgressFinish::AndLeave);
+        ProgressBar::new(entries.len() as u64).with_finish(indicatif::ProgressFinish::AndLeave);
     pb.set_style(
         ProgressStyle::with_template(
             "{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {msg} ({pos}/{len})",
         )
         .unwrap()
         .progress_chars("##-")
-        .tick_chars(&TICK_STRINGS),
+        .tick_chars(TICK_STRINGS),
     );
     pb.set_message("Packing files");
 
-    // Virtual path, real path
-    for (i, (vpath, rpath)) in file_paths.iter().enumerate().progress_with(pb) {
+    let mut data_files: Vec<Option<File>> = vec![];
+    for (vpath, rpath, options) in entries.iter().progress_with(pb) {
         let mut filedata = vec![];
-        let mut infile = File::open(rpath)?;
-        infile.read_to_end(&mut filedata)?;
-
-        let mut length = filedata.len() as u32;
-
-        let (hashcode, version, flags) = if vpath.to_ascii_lowercase().ends_with(".edb") {
-            // Use base filesize instead of full filesize
-            infile.seek(std::io::SeekFrom::Start(0x18))?;
-            length = infile.read_type(endian)?;
-
-            // TODO: Kind of inefficient to read from the file again instead of using the buffer
-            infile.seek(std::io::SeekFrom::Start(4))?;
-            (
-                infile.read_type(endian)?,
-                infile.read_type(endian)?,
-                infile.read_type(endian)?,
-            )
-        } else if vpath.to_ascii_lowercase().ends_with(".sfx") {
-            infile.seek(std::io::SeekFrom::Start(4))?;
-            (
-                infile.read_le::<u32>()? | 0x21000000,
-                infile.read_type::<u8>(endian)? as u32,
-                0,
-            )
-        } else {
-            (0x81000000 | i as u32, 0, 0)
-        };
-
-        if f_data.stream_position()? as usize + filedata.len() > split_size as usize {
-            filelist_num += 1;
-
-            let fp_data = format!("{}.{:03}", output_file, filelist_num);
-            f_data = File::create(&fp_data)?;
+        if let Some(rpath) = rpath {
+            File::open(rpath)?.read_to_end(&mut filedata)?;
         }
 
-        files.push((
-            vpath.clone(),
-            FileInfo5 {
-                version,
-                flags,
-                length,
-                hashcode,
-                fileloc: vec![FileLoc5 {
-                    addr: f_data.stream_position()? as u32,
-                    filelist_num,
-                }],
-            },
-        ));
-
-        f_data.write_all(&filedata)?;
-
-        // Pad next data to 2048 bytes
-        let unaligned_pos = f_data.stream_position()?;
-        if unaligned_pos & 0x7ff != 0 {
-            let remainder = unaligned_pos % 2048;
-            let aligned_pos = unaligned_pos + (2048 - remainder);
-            f_data.seek(std::io::SeekFrom::Start(aligned_pos))?;
+        let info = builder.add_file(vpath, &filedata, options)?.clone();
+        for loc in &info.fileloc {
+            let f_data = open_data_file(&mut data_files, &builder, &fp_info, loc.filelist_num)?;
+            f_data.seek(std::io::SeekFrom::Start(loc.addr as u64))?;
+            f_data.write_all(&filedata)?;
         }
     }
 
-    let filelist = EXFileListHeader5 {
-        version,
-        filesize: 0,
-        build_type: 1,
-        num_filelists: filelist_num as u16,
-        filename_list_offset: 0,
-        fileinfo: files.iter().map(|(_, v)| v.clone()).collect(),
-    };
-
-    let mut f_info = File::create(fp_info)?;
-    f_info.write_type(&filelist, endian)?;
-    let filename_offset = f_info.stream_position()?;
-
-    let filename_table_size = files.len() * 4;
-    let filename_data_offset = filename_offset + filename_table_size as u64;
-    let mut offset = filename_data_offset;
-    for (i, (v, _)) in files.iter().enumerate() {
-        let ptr_offset = filename_offset + i as u64 * 4;
-        f_info.write_type(&((offset - ptr_offset) as u32), endian)?;
-        offset += v.len() as u64 + 1;
-    }
-
-    f_info.seek(std::io::SeekFrom::Start(filename_data_offset))?;
-
-    for (i, (v, _)) in files.iter().enumerate() {
-        let mut path_buf = v.to_lowercase().as_bytes().to_vec();
-        path_buf.push(0);
-
-        if version >= 7 {
-            path::scramble_filename_v7(i as u32, &mut path_buf);
+    // Make sure every data file exists and is padded to the requested size
+    let data_file_sizes = builder.padded_data_file_sizes(&min_data_file_sizes);
+    for (i, &size) in data_file_sizes.iter().enumerate() {
+        let f_data = open_data_file(&mut data_files, &builder, &fp_info, i as u32)?;
+        if f_data.metadata()?.len() < size {
+            f_data.set_len(size)?;
         }
-
-        f_info.write_all(&path_buf)?;
     }
 
-    // Pad the file to 32 bytes
-    f_info.seek(std::io::SeekFrom::End(0))?;
-    let unaligned_size = f_info.stream_position()?;
-    if unaligned_size & 0x1f != 0 {
-        let remainder = 32 - (unaligned_size % 32);
-        f_info.write_all(&vec![0u8; remainder as usize])?;
-    }
-
-    let file_size = f_info.stream_position()?;
-
-    f_info.seek(std::io::SeekFrom::Start(4))?;
-    f_info.write_type(&(file_size as u32), endian)?;
-
-    f_info.seek(std::io::SeekFrom::Start(0x10))?;
-    f_info.write_type(&(filename_offset as u32 - 0x10), endian)?;
+    let filelist = builder.finish();
+    let mut f_info = File::create(fp_info)?;
+    filelist.write(&mut f_info)?;
 
     println!(
         "Successfully packed {} files into {} data files",
-        files.len(),
-        filelist_num + 1
+        filelist.files.len(),
+        data_file_sizes.len()
     );
 
     Ok(())
 }
 
-// TODO: Proper parser for scr files
-fn parse_scr_filelist<P: AsRef<Path>>(path: P) -> anyhow::Result<Vec<String>> {
-    let mut result = vec![];
-    let mut filebuf = String::new();
+/// Collects the files to pack, either in SCR order or in the order they are found on disk
+pub fn collect_files(
+    input_folder: &str,
+    drive_letter: char,
+    scr: Option<&ScrFile>,
+) -> anyhow::Result<Vec<PackEntry>> {
+    let pb = ProgressBar::new(0);
+    pb.set_style(
+        ProgressStyle::with_template("{spinner:.green} [{elapsed_precise}] {msg}")
+            .unwrap()
+            .progress_chars("##-")
+            .tick_chars(TICK_STRINGS),
+    );
+    pb.set_message("Locating files");
+
+    // Virtual path, real path
+    let mut file_paths = vec![];
+    for e in WalkDir::new(input_folder).sort_by_file_name() {
+        pb.tick();
+        let e = e?;
+        if e.file_type().is_file() {
+            let fpath = pathdiff::diff_paths(e.path(), input_folder)
+                .unwrap()
+                .to_string_lossy()
+                .replace('/', "\\");
+
+            file_paths.push((
+                format!("{drive_letter}:\\{fpath}"),
+                e.path().to_string_lossy().to_string(),
+            ))
+        }
+    }
 
-    let mut f = File::open(path).expect("Failed to open SCR file");
-    f.read_to_string(&mut filebuf)?;
+    pb.finish_and_clear();
+    println!("Located {} files", file_paths.len());
 
-    let mut in_filesection = false;
-    for l in filebuf.lines() {
-        let line = l.trim();
+    let Some(scr) = scr else {
+        return Ok(file_paths
+            .into_iter()
+            .map(|(vpath, rpath)| (vpath.to_lowercase(), Some(rpath), ScrFileOptions::default()))
+            .collect());
+    };
 
-        if in_filesection && line.len() > 3 {
-            result.push(line.to_owned())
+    println!("Reading files in SCR order");
+    let real_paths: HashMap<String, String> = file_paths
+        .iter()
+        .map(|(vpath, rpath)| (normalize_path(vpath), rpath.clone()))
+        .collect();
+    let vpaths: Vec<String> = file_paths.into_iter().map(|(vpath, _)| vpath).collect();
+
+    let resolved = scr.resolve(&vpaths);
+    for entry in scr.entries.iter().filter(|e| e.is_wildcard()) {
+        if !resolved.iter().any(|(e, _)| std::ptr::eq(*e, entry)) {
+            println!("Warning: SCR path {} yielded no results", entry.pattern);
         }
+    }
+
+    let mut entries = vec![];
+    for (entry, vpath) in resolved {
+        let rpath = real_paths.get(&normalize_path(&vpath)).cloned();
+        if entry.is_wildcard() {
+            entries.push((vpath.to_lowercase(), rpath, entry.options.clone()));
+        } else if rpath.is_some() || !entry.options.fileloc.is_empty() {
+            if rpath.is_none() {
+                println!(
+                    "Warning: SCR path '{vpath}' does not exist, no data will be written for it"
+                );
+            }
 
-        if line == "[FileList]" {
-            in_filesection = true;
-        } else if line.starts_with("[") {
-            in_filesection = false;
+            // Explicitly listed files keep the casing of the SCR
+            entries.push((vpath, rpath, entry.options.clone()));
+        } else {
+            println!("Warning: SCR path {vpath} yielded no results");
         }
     }
 
-    Ok(result)
+    println!("Loaded {} paths from SCR", entries.len());
+
+    Ok(entries)
+}
+
+fn open_data_file<'a>(
+    data_files: &'a mut Vec<Option<File>>,
+    builder: &FilelistBuilder,
+    fp_info: &Path,
+    filelist_num: u32,
+) -> anyhow::Result<&'a mut File> {
+    let i = filelist_num as usize;
+    if data_files.len() <= i {
+        data_files.resize_with(i + 1, || None);
+    }
+
+    if data_files[i].is_none() {
+        let path = data_file_path(
+            fp_info,
+            (!builder.is_single_data_file()).then_some(filelist_num),
+        );
+        data_files[i] =
+            Some(File::create(&path).context(format!("Failed to create data file {path:?}"))?);
+    }
+
+    Ok(data_files[i].as_mut().unwrap())
 }
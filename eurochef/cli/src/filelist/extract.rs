@@ -1,15 +1,8 @@
-use std::{
-    fs::File,
-    io::{BufReader, Read, Seek, Write},
-    path::Path,
-};
+use std::{fs::File, io::Write, path::Path};
 
 use anyhow::Context;
-use eurochef_edb::{
-    binrw::BinReaderExt,
-    versions::{transform_windows_path, Platform},
-};
-use eurochef_filelist::UXFileList;
+use eurochef_edb::versions::{transform_windows_path, Platform};
+use eurochef_filelist::{scr::ScrFile, FileFilter, FilelistVfs};
 use indicatif::{ProgressBar, ProgressIterator, ProgressStyle};
 
 use crate::filelist::TICK_STRINGS;
@@ -18,11 +11,11 @@ pub fn execute_command(
     filename: String,
     output_folder: String,
     create_scr: bool,
+    filter: FileFilter,
 ) -> anyhow::Result<()> {
     println!("Extracting {filename} to {output_folder}");
-    let mut file = File::open(&filename).context("Failed to open filelist header")?;
-    let mut reader = BufReader::new(&mut file);
-    let filelist = UXFileList::read(&mut reader)?;
+    let mut vfs = FilelistVfs::open_filelist(&filename)?;
+    let filelist = vfs.filelist().clone();
 
     std::fs::create_dir_all(&output_folder)?;
 
@@ -42,38 +35,16 @@ pub fn execute_command(
         println!("Detected platform {:?}", p);
     }
 
-    let mut scr_file = create_scr.then_some(
-        File::create(Path::new(&output_folder).join(format!(
-            "FileList{}.scr",
-            platform.map(|p| p.shorthand().to_uppercase()).unwrap_or(String::new())
-        )))
-        .context("Failed to create .scr file")?,
-    );
-
-    scr_file.as_mut().map(|f| {
-        writeln!(
-            f,
-            "[FileInfomation]
-
-[FileList]\n"
-        )
-        .expect("Failed to write scr file header");
-    });
-
-    let file_base = &filename[..filename.len() - 3];
-    let mut data_files = vec![];
-    if let Some(num_filelists) = filelist.num_filelists {
-        for i in 0..(num_filelists + 1) {
-            data_files.push(
-                File::open(format!("{}{:03}", file_base, i))
-                    .context(format!("Failed to open {}{:03}", file_base, i))?,
-            );
-        }
-    } else {
-        data_files.push(File::open(format!("{}DAT", file_base))?);
+    let indices = vfs.filter_files(&filter)?;
+    if !filter.is_empty() {
+        println!(
+            "{} of {} files match the filter",
+            indices.len(),
+            filelist.files.len()
+        );
     }
 
-    let pb = ProgressBar::new(filelist.files.len() as u64);
+    let pb = ProgressBar::new(indices.len() as u64);
     pb.set_style(
         ProgressStyle::with_template(
             "{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {msg} ({pos}/{len})",
@@ -84,14 +55,12 @@ pub fn execute_command(
     );
     pb.set_message("Extracting files");
 
-    for (i, (filename, info)) in filelist.files.iter().enumerate().progress_with(pb) {
+    let mut mismatches = 0;
+    for i in indices.iter().cloned().progress_with(pb) {
+        let (filename, info) = &filelist.files[i];
         let filename_fixed = filename.replace('\\', "/");
         let fpath = Path::new(&filename_fixed);
 
-        scr_file.as_mut().map(|f| {
-            writeln!(f, "{}", filename).expect("Failed to write file name to .scr");
-        });
-
         if fpath.to_string_lossy().is_empty() {
             println!(
                 "Skipping file {} with empty path (hashcode {:08x})",
@@ -100,36 +69,48 @@ pub fn execute_command(
             continue;
         }
 
-        let df = &mut data_files[info.filelist_num.unwrap_or(0) as usize];
-
-        df.seek(std::io::SeekFrom::Start(info.addr as u64))?;
-
-        let magic: u32 = df
-            .read_type(filelist.endian)
-            .expect("Failed to read file header");
-
-        let mut filesize = info.length;
+        let data = vfs.read_file(i)?;
 
-        if magic == 0x47454F4D {
-            df.seek(std::io::SeekFrom::Current(0x10))?;
-            filesize = df
-                .read_type(filelist.endian)
-                .expect("Failed to read GeoFile size");
+        // Duplicate copies are expected to be identical to the first one
+        for loc_index in vfs.mismatched_copies(i, &data)? {
+            let loc = &info.fileloc[loc_index];
+            mismatches += 1;
+            println!(
+                "Copy {} of {} (data file {}, address 0x{:x}) does not match the first copy",
+                loc_index, filename, loc.filelist_num, loc.addr
+            );
         }
 
-        df.seek(std::io::SeekFrom::Start(info.addr as u64))?;
-
-        let mut data = vec![0u8; filesize as usize];
-        df.read_exact(&mut data)?;
-
         let fpath_noprefix = Path::new(&output_folder).join(&fpath.to_str().unwrap()[3..]);
         std::fs::create_dir_all(fpath_noprefix.parent().unwrap())?;
         File::create(&fpath_noprefix)
             .context(format!("Failed to create output file {fpath_noprefix:?}"))?
-            .write(&data)?;
+            .write_all(&data)?;
     }
 
-    println!("Successfully extracted {} files", filelist.files.len());
+    println!("Successfully extracted {} files", indices.len());
+
+    if create_scr {
+        let scr_path = Path::new(&output_folder).join(format!(
+            "FileList{}.scr",
+            platform
+                .map(|p| p.shorthand().to_uppercase())
+                .unwrap_or(String::new())
+        ));
+
+        // The SCR records the exact layout of the filelist so it can be rebuilt identically
+        if !filter.is_empty() {
+            println!("Warning: the .scr file describes the whole filelist, rebuilding it requires every file to be extracted");
+        }
+
+        let scr = ScrFile::from_filelist(&filelist, vfs.data_file_sizes()?);
+        scr.write(&mut File::create(&scr_path).context("Failed to create .scr file")?)?;
+        println!("Wrote {}", scr_path.to_string_lossy());
+    }
+
+    if mismatches > 0 {
+        println!("Warning: {mismatches} duplicate file copies did not match their first copy");
+    }
 
     Ok(())
 }
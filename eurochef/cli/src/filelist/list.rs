@@ -0,0 +1,197 @@
+use std::{fs::File, io::BufReader, path::Path};
+
+use anyhow::Context;
+use eurochef_edb::{versions::transform_windows_path, Hashcode};
+use eurochef_filelist::{FileFilter, FilelistVfs, UXFileList};
+use eurochef_shared::{filesystem::path::DissectedFilelistPath, hashcodes::parse_hashcodes};
+use nohash_hasher::IntMap;
+use serde::Serialize;
+
+#[derive(Serialize)]
+pub struct ListEntry {
+    pub index: usize,
+    pub path: String,
+    pub length: u32,
+    pub hashcode: u32,
+    pub hashcode_name: Option<String>,
+    pub version: u32,
+    pub flags: u32,
+    pub locations: Vec<ListLocation>,
+}
+
+#[derive(Serialize)]
+pub struct ListLocation {
+    pub filelist_num: u32,
+    pub addr: u32,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ListFormat {
+    Table,
+    Json,
+    Csv,
+}
+
+pub fn execute_command(
+    filename: String,
+    format: ListFormat,
+    filter: FileFilter,
+    hashcodes_file: Option<String>,
+) -> anyhow::Result<()> {
+    let filelist = UXFileList::read(&mut BufReader::new(
+        File::open(&filename).with_context(|| format!("Failed to open {filename}"))?,
+    ))?;
+
+    // Some filelists contain the hashcodes header of the game
+    let embedded_hashcodes = if hashcodes_file.is_none() {
+        filelist
+            .files
+            .iter()
+            .position(|(path, _)| path.to_lowercase().ends_with("albert\\hashcodes.h"))
+    } else {
+        None
+    };
+
+    // The data files are only needed to check for geo files or to read the embedded hashcodes
+    let mut vfs = if filter.only_geo || embedded_hashcodes.is_some() {
+        Some(FilelistVfs::open_data_files(&filename, filelist.clone())?)
+    } else {
+        None
+    };
+
+    let hashcodes = match (&mut vfs, embedded_hashcodes) {
+        (Some(vfs), Some(index)) => {
+            let data = vfs.read_file(index)?;
+            parse_hashcodes(&String::from_utf8_lossy(&data))
+        }
+        _ => load_hashcodes(&filelist, &filename, hashcodes_file)?,
+    };
+
+    let indices = match &mut vfs {
+        Some(vfs) => vfs.filter_files(&filter)?,
+        None => (0..filelist.files.len())
+            .filter(|&i| filter.matches_entry(&filelist.files[i].0, &filelist.files[i].1))
+            .collect(),
+    };
+
+    let entries: Vec<ListEntry> = indices
+        .into_iter()
+        .map(|index| (index, &filelist.files[index]))
+        .map(|(index, (path, info))| ListEntry {
+            index,
+            path: path.clone(),
+            length: info.length,
+            hashcode: info.hashcode,
+            hashcode_name: hashcodes.get(&info.hashcode).cloned(),
+            version: info.version,
+            flags: info.flags,
+            locations: info
+                .fileloc
+                .iter()
+                .map(|l| ListLocation {
+                    filelist_num: l.filelist_num,
+                    addr: l.addr,
+                })
+                .collect(),
+        })
+        .collect();
+
+    match format {
+        ListFormat::Table => {
+            println!(
+                "{:>5} {:>10} {:>8} {:>6} {:>8} {:<16} path",
+                "index", "length", "hashcode", "ver", "flags", "location(s)"
+            );
+            for e in &entries {
+                println!(
+                    "{:>5} {:>10} {:08x} {:>6} {:08x} {:<16} {}{}",
+                    e.index,
+                    e.length,
+                    e.hashcode,
+                    e.version,
+                    e.flags,
+                    format_locations(&e.locations, " "),
+                    e.path,
+                    e.hashcode_name
+                        .as_ref()
+                        .map(|n| format!(" ({n})"))
+                        .unwrap_or_default()
+                );
+            }
+
+            println!(
+                "{} of {} files (version {})",
+                entries.len(),
+                filelist.files.len(),
+                filelist.version
+            );
+        }
+        ListFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(&entries)?);
+        }
+        ListFormat::Csv => {
+            println!("index,path,length,hashcode,hashcode_name,version,flags,locations");
+            for e in &entries {
+                println!(
+                    "{},{},{},0x{:08x},{},{},0x{:x},{}",
+                    e.index,
+                    csv_escape(&e.path),
+                    e.length,
+                    e.hashcode,
+                    csv_escape(e.hashcode_name.as_deref().unwrap_or_default()),
+                    e.version,
+                    e.flags,
+                    format_locations(&e.locations, ";")
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Loads hashcodes from the given file, or from the hashcodes.h file of the game the filelist belongs to
+fn load_hashcodes(
+    filelist: &UXFileList,
+    filename: &str,
+    hashcodes_file: Option<String>,
+) -> anyhow::Result<IntMap<Hashcode, String>> {
+    if let Some(path) = hashcodes_file {
+        let data = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read hashcodes file {path}"))?;
+        return Ok(parse_hashcodes(&data));
+    }
+
+    let dissected = filelist
+        .files
+        .iter()
+        .find_map(|(path, _)| DissectedFilelistPath::dissect(transform_windows_path(path)));
+
+    Ok(dissected
+        .map(|mut d| {
+            // Paths in the filelist are relative to the directory the filelist is in
+            d.root = Path::new(filename)
+                .parent()
+                .unwrap_or(Path::new(""))
+                .to_string_lossy()
+                .to_string();
+            eurochef_shared::filesystem::load_hashcodes(&d, false)
+        })
+        .unwrap_or_default())
+}
+
+fn format_locations(locations: &[ListLocation], separator: &str) -> String {
+    locations
+        .iter()
+        .map(|l| format!("{}:0x{:x}", l.filelist_num, l.addr))
+        .collect::<Vec<String>>()
+        .join(separator)
+}
+
+fn csv_escape(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
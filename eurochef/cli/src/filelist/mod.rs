@@ -2,3 +2,6 @@ const TICK_STRINGS: &'static str = "⠁⠂⠄⡀⢀⠠⠐⠈";
 
 pub mod create;
 pub mod extract;
+pub mod list;
+pub mod patch;
+pub mod verify;
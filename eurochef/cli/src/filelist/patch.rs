@@ -0,0 +1,52 @@
+use std::{fs::File, io::Read};
+
+use anyhow::Context;
+use eurochef_filelist::patch::{self, FilePatch, PatchOutcome, PatchTarget};
+
+pub fn execute_command(
+    filename: String,
+    files: Vec<String>,
+    split_size: u32,
+) -> anyhow::Result<()> {
+    let mut patches = vec![];
+    for f in &files {
+        let (target, source) = f
+            .split_once('=')
+            .with_context(|| format!("Invalid file argument '{f}', expected TARGET=FILE"))?;
+
+        let target = if let Some(hashcode) = target.strip_prefix("0x") {
+            PatchTarget::Hashcode(
+                u32::from_str_radix(hashcode, 16)
+                    .with_context(|| format!("Invalid hashcode '{target}'"))?,
+            )
+        } else {
+            PatchTarget::Path(target.to_string())
+        };
+
+        let mut data = vec![];
+        File::open(source)
+            .with_context(|| format!("Failed to open {source}"))?
+            .read_to_end(&mut data)?;
+
+        patches.push(FilePatch { target, data });
+    }
+
+    println!("Patching {} file(s) in {filename}", patches.len());
+    for outcome in patch::patch_filelist(&filename, &patches, split_size)? {
+        match outcome {
+            PatchOutcome::Replaced {
+                path,
+                previous_copies,
+            } => {
+                if previous_copies > 1 {
+                    println!("Replaced {path} (stored once instead of {previous_copies} copies)")
+                } else {
+                    println!("Replaced {path}")
+                }
+            }
+            PatchOutcome::Added(path) => println!("Added {path}"),
+        }
+    }
+
+    Ok(())
+}
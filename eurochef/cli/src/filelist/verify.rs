@@ -0,0 +1,127 @@
+use std::{
+    fs::File,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use eurochef_filelist::{
+    builder::FilelistBuilder,
+    scr::ScrFile,
+    verify::{diff_data_file_sizes, diff_file_data, diff_filelists, diff_header_bytes},
+    FilelistVfs,
+};
+use indicatif::{ProgressBar, ProgressIterator, ProgressStyle};
+
+use crate::filelist::{create::collect_files, TICK_STRINGS};
+
+/// Rebuilds a filelist from an extracted folder in memory and compares it against the original
+///
+/// Data is only compared at the locations of the files, padding between files is not checked.
+/// Data file sizes are compared against the rebuilt data files padded to the sizes recorded in the SCR file, the same way `filelist create` pads them.
+pub fn execute_command(
+    filename: String,
+    input_folder: String,
+    drive_letter: char,
+    scr_file: Option<String>,
+    max_divergences: usize,
+) -> anyhow::Result<()> {
+    println!("Verifying {input_folder} against {filename}");
+    let mut vfs = FilelistVfs::open_filelist(&filename)?;
+    let original = vfs.filelist().clone();
+
+    let scr_path = match scr_file {
+        Some(s) => PathBuf::from(s),
+        None => find_scr(&input_folder)?,
+    };
+    println!("Using SCR file {}", scr_path.to_string_lossy());
+    let scr = ScrFile::read(&mut File::open(&scr_path).context("Failed to open SCR file")?)
+        .context("Failed to read SCR file")?;
+
+    let mut builder = FilelistBuilder::new(original.version, original.endian)?;
+    builder.apply_scr_info(&scr.info)?;
+
+    let entries = collect_files(&input_folder, drive_letter, Some(&scr))?;
+
+    let pb = ProgressBar::new(entries.len() as u64);
+    pb.set_style(
+        ProgressStyle::with_template(
+            "{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {msg} ({pos}/{len})",
+        )
+        .unwrap()
+        .progress_chars("##-")
+        .tick_chars(TICK_STRINGS),
+    );
+    pb.set_message("Comparing file data");
+
+    let mut data_divergences = vec![];
+    for (index, (vpath, rpath, options)) in entries.iter().enumerate().progress_with(pb) {
+        let mut filedata = vec![];
+        if let Some(rpath) = rpath {
+            File::open(rpath)?.read_to_end(&mut filedata)?;
+        }
+
+        let info = builder.add_file(vpath, &filedata, options)?.clone();
+        data_divergences.extend(diff_file_data(
+            &mut vfs,
+            index,
+            vpath,
+            &info.fileloc,
+            &filedata,
+        )?);
+    }
+
+    let original_sizes = vfs.data_file_sizes()?;
+    let rebuilt_sizes = builder.padded_data_file_sizes(&scr.info.data_file_sizes);
+
+    let rebuilt = builder.finish();
+    let mut divergences = diff_filelists(&original, &rebuilt);
+
+    let mut original_header = vec![];
+    File::open(&filename)?.read_to_end(&mut original_header)?;
+    divergences.extend(diff_header_bytes(&original_header, &rebuilt)?);
+
+    divergences.extend(diff_data_file_sizes(&original_sizes, &rebuilt_sizes));
+    divergences.extend(data_divergences);
+
+    if divergences.is_empty() {
+        println!(
+            "Rebuilt filelist is identical to {filename} ({} files)",
+            original.files.len()
+        );
+        return Ok(());
+    }
+
+    for d in divergences.iter().take(max_divergences) {
+        println!("{d}");
+    }
+
+    if divergences.len() > max_divergences {
+        println!("... and {} more", divergences.len() - max_divergences);
+    }
+
+    anyhow::bail!(
+        "Found {} divergence(s) between the rebuilt filelist and {filename}",
+        divergences.len()
+    )
+}
+
+/// Finds the SCR file written by `filelist extract --create-scr`
+fn find_scr(input_folder: &str) -> anyhow::Result<PathBuf> {
+    for e in std::fs::read_dir(input_folder)? {
+        let path = e?.path();
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+
+        if name.starts_with("filelist") && name.ends_with(".scr") {
+            return Ok(path);
+        }
+    }
+
+    anyhow::bail!(
+        "No FileList*.scr found in {}, specify one with --scr-file",
+        Path::new(input_folder).to_string_lossy()
+    )
+}
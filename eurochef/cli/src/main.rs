@@ -8,6 +8,7 @@ use anyhow::Context;
 use clap::{Parser, Subcommand};
 use clap_num::maybe_hex;
 use eurochef_edb::versions::Platform;
+use eurochef_filelist::FileFilter;
 use tracing::metadata::LevelFilter;
 use tracing_subscriber::{
     prelude::__tracing_subscriber_SubscriberExt, util::SubscriberInitExt, EnvFilter,
@@ -128,6 +129,34 @@ enum EdbCommand {
         #[arg(long)]
         no_apngs: bool,
     },
+    /// Export every supported section of a file to a folder with a YAML file and texture images
+    Geofile {
+        /// .edb file to read
+        filename: String,
+
+        /// Output folder (default: "./geofiles/{filename}/")
+        output_folder: Option<String>,
+
+        /// Override for platform detection
+        #[arg(value_enum, short, long, ignore_case = true)]
+        platform: Option<PlatformArg>,
+
+        /// hashcodes.h file used to name the hashcodes in the file
+        #[arg(long)]
+        hashcodes: Option<String>,
+    },
+    /// Write a file back through the EDB writer and check that the result is identical
+    Rewrite {
+        /// .edb file to read
+        filename: String,
+
+        /// File to write the result to
+        output_file: Option<String>,
+
+        /// Override for platform detection
+        #[arg(value_enum, short, long, ignore_case = true)]
+        platform: Option<PlatformArg>,
+    },
     /// Extract animations (!!MAJOR WIP!!)
     Animations {
         /// .edb file to read
@@ -157,6 +186,22 @@ enum FilelistCommand {
         /// Create a .scr file in the output folder
         #[arg(short = 's', long)]
         create_scr: bool,
+
+        /// Only extract files matching these glob patterns ('*' matches within a directory, '?' and '#' match a single character)
+        #[arg(long, short)]
+        include: Vec<String>,
+
+        /// Don't extract files matching these glob patterns
+        #[arg(long, short = 'x')]
+        exclude: Vec<String>,
+
+        /// Only extract files with these hashcodes
+        #[arg(long, value_parser = maybe_hex::<u32>)]
+        hashcode: Vec<u32>,
+
+        /// Only extract geo (.edb) files
+        #[arg(long)]
+        only_geo: bool,
     },
     /// Create a new filelist from a folder
     Create {
@@ -170,26 +215,99 @@ enum FilelistCommand {
         #[arg(long, short = 'l', default_value_t = 'x')]
         drive_letter: char,
 
-        /// Supported versions: 5, 6, 7
-        #[arg(long, short, default_value_t = 7)]
-        version: u32,
+        /// Supported versions: 4, 5, 6, 7, 9, 10
+        ///
+        /// Defaults to the version from the SCR file, or 7 without one
+        #[arg(long, short)]
+        version: Option<u32>,
 
         #[arg(value_enum, short, long, ignore_case = true)]
         platform: PlatformArg,
 
-        /// Maximum size per data file, might be overridden by a .scr file
+        /// Maximum size per data file
+        ///
+        /// Defaults to the split size from the SCR file, or 0x80000000 without one
+        #[arg(long, short = 'z', value_parser = maybe_hex::<u32>)]
+        split_size: Option<u32>,
+
+        /// .scr file to read the file order and options from. A --version or --split-size that conflicts with the .scr file is rejected
+        #[arg(long, short)]
+        scr_file: Option<String>,
+    },
+    /// Replace or add files in an existing filelist without repacking it
+    Patch {
+        /// .bin file to patch
+        filename: String,
+
+        /// Files to replace or add, formatted as TARGET=FILE. TARGET is either a path inside the filelist or a hashcode prefixed with 0x
+        #[arg(required = true)]
+        files: Vec<String>,
+
+        /// Maximum size per data file
         #[arg(long, short = 'z', default_value_t = 0x80000000, value_parser = maybe_hex::<u32>)]
         split_size: u32,
+    },
+    /// List the files in a filelist without extracting them
+    List {
+        /// .bin file to use
+        filename: String,
+
+        /// Output as JSON
+        #[arg(long, conflicts_with = "csv")]
+        json: bool,
+
+        /// Output as CSV
+        #[arg(long)]
+        csv: bool,
+
+        /// Only list files matching these glob patterns ('*' matches within a directory, '?' and '#' match a single character)
+        #[arg(long, short)]
+        include: Vec<String>,
+
+        /// Don't list files matching these glob patterns
+        #[arg(long, short = 'x')]
+        exclude: Vec<String>,
+
+        /// Only list files with these hashcodes
+        #[arg(long, value_parser = maybe_hex::<u32>)]
+        hashcode: Vec<u32>,
+
+        /// Only list geo (.edb) files
+        #[arg(long)]
+        only_geo: bool,
+
+        /// hashcodes.h file to resolve hashcode names with. Defaults to the hashcodes.h of the game the filelist belongs to
+        #[arg(long)]
+        hashcodes: Option<String>,
+    },
+    /// Check that a folder created by `extract --create-scr` rebuilds into an identical filelist
+    Verify {
+        /// Original .bin file
+        filename: String,
+
+        /// Folder containing the extracted files
+        input_folder: String,
 
-        /// .scr file to read options from (currently doesnt support wildcards)
+        #[arg(long, short = 'l', default_value_t = 'x')]
+        drive_letter: char,
+
+        /// .scr file to rebuild with. Defaults to the FileList*.scr file in the input folder
         #[arg(long, short)]
         scr_file: Option<String>,
+
+        /// Maximum number of divergences to print
+        #[arg(long, short = 'n', default_value_t = 20)]
+        max_divergences: usize,
     },
 }
 
 pub fn main() -> anyhow::Result<()> {
     tracing_subscriber::registry()
-        .with(tracing_subscriber::fmt::layer().without_time())
+        .with(
+            tracing_subscriber::fmt::layer()
+                .without_time()
+                .with_writer(std::io::stderr),
+        )
         .with(
             EnvFilter::builder()
                 .with_default_directive(LevelFilter::INFO.into())
@@ -238,6 +356,17 @@ fn handle_edb(cmd: EdbCommand) -> anyhow::Result<()> {
             format,
             no_apngs,
         } => edb::textures::execute_command(filename, platform, output_folder, format, no_apngs),
+        EdbCommand::Geofile {
+            filename,
+            output_folder,
+            platform,
+            hashcodes,
+        } => edb::geofile::execute_command(filename, platform, output_folder, hashcodes),
+        EdbCommand::Rewrite {
+            filename,
+            output_file,
+            platform,
+        } => edb::rewrite::execute_command(filename, platform, output_file),
         EdbCommand::Animations {
             filename,
             platform,
@@ -252,8 +381,22 @@ fn handle_filelist(cmd: FilelistCommand) -> anyhow::Result<()> {
             filename,
             output_folder,
             create_scr,
-        } => filelist::extract::execute_command(filename, output_folder, create_scr)
-            .context("Failed to extract filelist"),
+            include,
+            exclude,
+            hashcode,
+            only_geo,
+        } => filelist::extract::execute_command(
+            filename,
+            output_folder,
+            create_scr,
+            FileFilter {
+                include,
+                exclude,
+                hashcodes: hashcode,
+                only_geo,
+            },
+        )
+        .context("Failed to extract filelist"),
         FilelistCommand::Create {
             input_folder,
             output_file,
@@ -272,5 +415,53 @@ fn handle_filelist(cmd: FilelistCommand) -> anyhow::Result<()> {
             scr_file,
         )
         .context("Failed to create filelist"),
+        FilelistCommand::Patch {
+            filename,
+            files,
+            split_size,
+        } => filelist::patch::execute_command(filename, files, split_size)
+            .context("Failed to patch filelist"),
+        FilelistCommand::List {
+            filename,
+            json,
+            csv,
+            include,
+            exclude,
+            hashcode,
+            only_geo,
+            hashcodes,
+        } => {
+            let format = if json {
+                filelist::list::ListFormat::Json
+            } else if csv {
+                filelist::list::ListFormat::Csv
+            } else {
+                filelist::list::ListFormat::Table
+            };
+
+            let filter = FileFilter {
+                include,
+                exclude,
+                hashcodes: hashcode,
+                only_geo,
+            };
+
+            filelist::list::execute_command(filename, format, filter, hashcodes)
+                .context("Failed to list filelist")
+        }
+        FilelistCommand::Verify {
+            filename,
+            input_folder,
+            drive_letter,
+            scr_file,
+            max_divergences,
+        } => filelist::verify::execute_command(
+            filename,
+            input_folder,
+            drive_letter,
+            scr_file,
+            max_divergences,
+        )
+        .context("Failed to verify filelist"),
     }
 }
@@ -219,7 +219,11 @@ impl EurochefApp {
                     let file = File::open(&path)?;
                     let reader = BufReader::new(file);
 
-                    a.insert(EdbFile::new(Box::new(reader), platform)?)
+                    a.insert(EdbFile::with_game(
+                        Box::new(reader),
+                        platform,
+                        Some(self.game.as_str()),
+                    )?)
                 }
             }
         } else {
@@ -308,7 +312,7 @@ impl EurochefApp {
         }
 
         self.render_store.write().purge(true);
-        let mut edb = EdbFile::new(reader, platform)?;
+        let mut edb = EdbFile::with_game(reader, platform, Some(self.game.as_str()))?;
         let header = edb.header.clone();
 
         self.current_panel = Panel::FileInfo;
@@ -787,7 +787,9 @@ fn read_entity_identifiable(
 ) -> anyhow::Result<(EXGeoEntity, ProcessedEntityMesh)> {
     edb.seek(std::io::SeekFrom::Start(address as u64))?;
 
-    let ent = edb.read_type_args(edb.endian, (edb.header.version, edb.platform))?;
+    let ent = edb.collect_references(|edb| {
+        edb.read_type_args::<EXGeoEntity>(edb.endian, (edb.header.version, edb.platform))
+    })?;
 
     let mut vertex_data = vec![];
     let mut indices = vec![];
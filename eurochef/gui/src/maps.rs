@@ -143,7 +143,9 @@ pub fn read_from_file(edb: &mut EdbFile) -> Vec<ProcessedMap> {
             .unwrap();
 
         let xmap = edb
-            .read_type_args::<EXGeoMap>(edb.endian, (header.version,))
+            .collect_references(|edb| {
+                edb.read_type_args::<EXGeoMap>(edb.endian, (header.version, edb.profile))
+            })
             .context("Failed to read map")
             .unwrap();
 
@@ -158,13 +160,9 @@ pub fn read_from_file(edb: &mut EdbFile) -> Vec<ProcessedMap> {
         };
 
         for z in &xmap.zones {
-            let entity_offset = header.refpointer_list[z.entity_refptr as usize].address;
-            edb.seek(std::io::SeekFrom::Start(entity_offset as u64))
-                .context("Mapzone refptr pointer to a non-entity object!")
-                .unwrap();
-
             let ent = edb
-                .read_type_args::<EXGeoEntity>(edb.endian, (header.version, edb.platform))
+                .read_refpointer_entity(z.entity_refptr as usize)
+                .context("Failed to read mapzone entity")
                 .unwrap();
 
             if let EXGeoEntity::MapZone(mapzone) = ent {
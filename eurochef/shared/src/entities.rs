@@ -5,14 +5,14 @@ use eurochef_edb::edb::EdbFile;
 use eurochef_edb::{
     binrw::BinReaderExt,
     common::{EXVector, EXVector2, EXVector3},
-    edb::DatabaseReader,
     entity::EXGeoEntity,
     entity_mesh::EXGeoEntityTriStrip,
     versions::Platform,
 };
+use serde::{Deserialize, Serialize};
 use tracing::error;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct TriStrip {
     pub start_index: u32,
     pub index_count: u32,
@@ -22,7 +22,7 @@ pub struct TriStrip {
     pub tri_count: u32,
 }
 
-#[derive(Debug, Copy, Clone, Pod, Zeroable)]
+#[derive(Debug, Copy, Clone, Pod, Zeroable, Serialize, Deserialize)]
 #[repr(C)]
 pub struct UXVertex {
     pub pos: EXVector3,
@@ -61,14 +61,6 @@ pub fn read_entity(
             }
         }
         EXGeoEntity::Mesh(mesh) => {
-            if let Some(edb) = edb.downcast_to_edbfile() {
-                for v in &mesh.texture_list {
-                    edb.add_reference_internal(
-                        edb.header.texture_list[*v as usize].common.hashcode,
-                    );
-                }
-            }
-
             if edb.platform == Platform::Ps2 {
                 panic!("PS2 support is disabled");
             }
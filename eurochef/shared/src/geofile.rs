@@ -0,0 +1,528 @@
+//! Platform- and version-independent representation of a whole EDB file
+//!
+//! A [`UXGeoFile`] owns all the data eurochef can read from an EDB file. It can be saved to a folder containing a YAML
+//! file with the structure of the file and a PNG image for every texture frame, which makes it easy to diff and edit.
+
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{BufReader, BufWriter, Seek, SeekFrom},
+    path::Path,
+};
+
+use anyhow::Context;
+use eurochef_edb::{
+    anim::EXGeoBaseAnimSkin,
+    binrw::BinReaderExt,
+    edb::EdbFile,
+    entity::EXGeoEntity,
+    map::{EXGeoLight, EXGeoMap, EXGeoPath, EXGeoPlacement},
+    versions::Platform,
+    Hashcode,
+};
+use nohash_hasher::IntMap;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::{
+    entities::{read_entity, TriStrip, UXVertex},
+    maps::UXGeoTrigger,
+    script::UXGeoScript,
+    spreadsheets::UXGeoSpreadsheet,
+    textures::UXGeoTexture,
+};
+
+const GEOFILE_YAML: &str = "geofile.yml";
+const TEXTURE_DIR: &str = "textures";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UXGeoFile {
+    pub hashcode: Hashcode,
+    pub version: u32,
+    pub flags: u32,
+    /// Platform the file was read from, as a shorthand (eg. 'pc', 'xe')
+    pub platform: String,
+
+    /// Names of the hashcodes used by this file, if a hashcodes file was available
+    pub hashcode_names: BTreeMap<Hashcode, String>,
+    /// (file, object) hashcodes of objects in other files used by this file
+    pub external_references: Vec<(Hashcode, Hashcode)>,
+
+    pub entities: Vec<UXGeoFileEntity>,
+    pub textures: Vec<UXGeoFileTexture>,
+    pub maps: Vec<UXGeoFileMap>,
+    pub scripts: Vec<UXGeoScript>,
+    pub spreadsheets: Vec<UXGeoFileSpreadsheet>,
+    pub anim_skins: Vec<UXGeoFileAnimSkin>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UXGeoFileEntity {
+    pub hashcode: Hashcode,
+    pub vertices: Vec<UXVertex>,
+    pub indices: Vec<u32>,
+    pub strips: Vec<TriStrip>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UXGeoFileTexture {
+    pub hashcode: Hashcode,
+    pub width: u16,
+    pub height: u16,
+    pub depth: u16,
+    pub flags: u32,
+    pub game_flags: u16,
+    pub scroll: [i16; 2],
+    pub framerate: u8,
+    pub color: [u8; 4],
+
+    /// (file, texture) hashcodes of a texture in another file that is used instead of this one
+    pub external_texture: Option<(Hashcode, Hashcode)>,
+
+    /// Decoded RGBA frame data. Stored as separate PNG images on disk
+    #[serde(skip)]
+    pub frames: Vec<Vec<u8>>,
+    pub frame_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UXGeoFileMap {
+    pub hashcode: Hashcode,
+    pub paths: Vec<UXGeoFilePath>,
+    pub placements: Vec<UXGeoFilePlacement>,
+    pub lights: Vec<UXGeoFileLight>,
+    pub triggers: Vec<UXGeoTrigger>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UXGeoFilePath {
+    pub hashcode: Hashcode,
+    pub position: [f32; 3],
+    pub flags: u32,
+    pub path_type: u16,
+    pub bounds_box: [[f32; 4]; 2],
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UXGeoFilePlacement {
+    pub hashcode: Hashcode,
+    pub object_ref: Hashcode,
+    pub position: [f32; 3],
+    pub rotation: [f32; 3],
+    pub scale: [f32; 3],
+    pub flags: u32,
+    pub engine_flags: u16,
+    pub map_on: u16,
+    pub light_set: u16,
+    pub group: i16,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UXGeoFileLight {
+    pub hashcode: Hashcode,
+    pub position: [f32; 3],
+    pub beam: [f32; 3],
+    pub flags: u32,
+    pub light_type: u16,
+    pub beam_angle: u16,
+    pub colour: [u8; 4],
+    pub radius: f32,
+    pub max_effect_fraction: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UXGeoFileSpreadsheet {
+    pub hashcode: Hashcode,
+    pub spreadsheet: UXGeoSpreadsheet,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UXGeoFileAnimSkin {
+    pub hashcode: Hashcode,
+    pub bone_count: u32,
+    pub bone_rotations: Vec<[f32; 4]>,
+    /// (link index, max index, flags) for every bone
+    pub hierarchy: Vec<(u16, u16, u16)>,
+    /// (section index, entity index, morph index) of every entity used by the skin
+    pub entities: Vec<(u32, u32, i32)>,
+}
+
+impl UXGeoFile {
+    /// Reads every supported section of a file. Objects that fail to read are skipped with a warning
+    pub fn read(edb: &mut EdbFile, hashcodes: &IntMap<Hashcode, String>) -> anyhow::Result<Self> {
+        let header = edb.header.clone();
+
+        let mut file = UXGeoFile {
+            hashcode: header.hashcode,
+            version: header.version,
+            flags: header.flags,
+            platform: edb.platform.shorthand().to_string(),
+            hashcode_names: BTreeMap::new(),
+            external_references: vec![],
+            entities: vec![],
+            textures: vec![],
+            maps: vec![],
+            scripts: vec![],
+            spreadsheets: vec![],
+            anim_skins: vec![],
+        };
+
+        file.read_entities(edb);
+        file.read_textures(edb);
+        file.read_maps(edb)?;
+
+        file.scripts = UXGeoScript::read_all(edb).context("Failed to read scripts")?;
+        file.spreadsheets = UXGeoSpreadsheet::read_all(edb)
+            .context("Failed to read spreadsheets")?
+            .into_iter()
+            .map(|(hashcode, spreadsheet)| UXGeoFileSpreadsheet {
+                hashcode,
+                spreadsheet,
+            })
+            .collect();
+
+        file.read_anim_skins(edb);
+        file.external_references = edb.external_references.clone();
+        file.resolve_hashcodes(hashcodes);
+
+        Ok(file)
+    }
+
+    fn read_entities(&mut self, edb: &mut EdbFile) {
+        // read_entity doesn't support PS2 meshes
+        if edb.platform == Platform::Ps2 {
+            warn!("Skipping entities, PS2 entities are not supported");
+            return;
+        }
+
+        let header = edb.header.clone();
+        for e in header.entity_list.iter() {
+            let result = (|| -> anyhow::Result<UXGeoFileEntity> {
+                edb.seek(SeekFrom::Start(e.common.address as u64))?;
+                let ent = edb.collect_references(|edb| {
+                    edb.read_type_args::<EXGeoEntity>(edb.endian, (header.version, edb.platform))
+                })?;
+
+                let mut entity = UXGeoFileEntity {
+                    hashcode: e.common.hashcode,
+                    vertices: vec![],
+                    indices: vec![],
+                    strips: vec![],
+                };
+                read_entity(
+                    &ent,
+                    &mut entity.vertices,
+                    &mut entity.indices,
+                    &mut entity.strips,
+                    edb,
+                    4,
+                    false,
+                    true,
+                )?;
+
+                Ok(entity)
+            })();
+
+            match result {
+                Ok(entity) => self.entities.push(entity),
+                Err(err) => warn!("Failed to read entity {:x}: {err}", e.common.hashcode),
+            }
+        }
+    }
+
+    fn read_textures(&mut self, edb: &mut EdbFile) {
+        for (_, it) in UXGeoTexture::read_all(edb) {
+            match it.data {
+                Ok(t) => self.textures.push(UXGeoFileTexture {
+                    hashcode: it.hashcode,
+                    width: t.width,
+                    height: t.height,
+                    depth: t.depth,
+                    flags: t.flags,
+                    game_flags: t.game_flags,
+                    scroll: t.scroll,
+                    framerate: t.framerate,
+                    color: t.color,
+                    external_texture: t.external_texture,
+                    frame_count: t.frames.len(),
+                    frames: t.frames,
+                }),
+                Err(err) => warn!("Failed to read texture {:x}: {err}", it.hashcode),
+            }
+        }
+    }
+
+    fn read_maps(&mut self, edb: &mut EdbFile) -> anyhow::Result<()> {
+        let header = edb.header.clone();
+        for m in header.map_list.iter() {
+            edb.seek(SeekFrom::Start(m.address as u64))?;
+            let map = match edb.collect_references(|edb| {
+                edb.read_type_args::<EXGeoMap>(edb.endian, (header.version, edb.profile))
+            }) {
+                Ok(map) => map,
+                Err(err) => {
+                    warn!("Failed to read map {:x}: {err}", m.hashcode);
+                    continue;
+                }
+            };
+
+            let triggers = map
+                .trigger_header
+                .triggers
+                .iter()
+                .map(|t| {
+                    let trig = &t.trigger;
+                    let ttype = &map.trigger_header.trigger_types[trig.type_index as usize];
+
+                    UXGeoTrigger {
+                        link_ref: t.link_ref,
+                        ttype: format!("Trig_{}", ttype.trig_type),
+                        tsubtype: if ttype.trig_subtype != 0 && ttype.trig_subtype != 0x42000001 {
+                            Some(format!("TrigSub_{}", ttype.trig_subtype))
+                        } else {
+                            None
+                        },
+                        debug: trig.debug,
+                        game_flags: trig.game_flags,
+                        trig_flags: trig.trig_flags,
+                        position: trig.position,
+                        rotation: trig.rotation,
+                        scale: trig.scale,
+                        data: trig.data.to_vec(),
+                        links: trig.links.to_vec(),
+                        extra_data: vec![],
+                    }
+                })
+                .collect();
+
+            self.maps.push(UXGeoFileMap {
+                hashcode: m.hashcode,
+                paths: map.paths.iter().map(Into::into).collect(),
+                placements: map.placements.iter().map(Into::into).collect(),
+                lights: map.lights.iter().map(Into::into).collect(),
+                triggers,
+            });
+        }
+
+        Ok(())
+    }
+
+    fn read_anim_skins(&mut self, edb: &mut EdbFile) {
+        let header = edb.header.clone();
+        for s in header.animskin_list.iter() {
+            let result = edb
+                .seek(SeekFrom::Start(s.common.address as u64))
+                .map_err(Into::into)
+                .and_then(|_| {
+                    edb.read_type_args::<EXGeoBaseAnimSkin>(edb.endian, (header.version,))
+                });
+
+            match result {
+                Ok(skin) => self.anim_skins.push(UXGeoFileAnimSkin {
+                    hashcode: s.common.hashcode,
+                    bone_count: skin.bone_count,
+                    bone_rotations: skin.rot_data.to_vec(),
+                    hierarchy: skin
+                        .hier_data
+                        .iter()
+                        .map(|h| (h.link_index, h.max_index, h.flags))
+                        .collect(),
+                    entities: skin
+                        .entities
+                        .iter()
+                        .map(|e| (e.section_index, e.entity_index, e.morph_index))
+                        .collect(),
+                }),
+                Err(err) => warn!("Failed to read anim skin {:x}: {err}", s.common.hashcode),
+            }
+        }
+    }
+
+    /// Fills `hashcode_names` with the names of the hashcodes of all objects in this file
+    pub fn resolve_hashcodes(&mut self, hashcodes: &IntMap<Hashcode, String>) {
+        let mut used = vec![self.hashcode];
+        used.extend(self.entities.iter().map(|e| e.hashcode));
+        used.extend(self.textures.iter().map(|t| t.hashcode));
+        used.extend(self.scripts.iter().map(|s| s.hashcode));
+        used.extend(self.spreadsheets.iter().map(|s| s.hashcode));
+        used.extend(self.anim_skins.iter().map(|s| s.hashcode));
+        for m in &self.maps {
+            used.push(m.hashcode);
+            used.extend(m.paths.iter().map(|p| p.hashcode));
+            used.extend(m.lights.iter().map(|l| l.hashcode));
+            used.extend(m.placements.iter().map(|p| p.hashcode));
+            used.extend(m.placements.iter().map(|p| p.object_ref));
+        }
+
+        for hc in used {
+            if let Some(name) = hashcodes.get(&hc) {
+                self.hashcode_names.insert(hc, name.clone());
+            }
+        }
+    }
+
+    /// Saves the file to a folder, as a YAML file with a PNG image for every texture frame
+    pub fn save<P: AsRef<Path>>(&self, folder: P) -> anyhow::Result<()> {
+        let folder = folder.as_ref();
+        let texture_folder = folder.join(TEXTURE_DIR);
+        std::fs::create_dir_all(&texture_folder)?;
+
+        let mut writer = BufWriter::new(File::create(folder.join(GEOFILE_YAML))?);
+        serde_yaml::to_writer(&mut writer, self)?;
+
+        for t in &self.textures {
+            for (i, frame) in t.frames.iter().enumerate() {
+                image::save_buffer(
+                    texture_folder.join(texture_frame_filename(t.hashcode, i)),
+                    frame,
+                    t.width as u32,
+                    t.height as u32,
+                    image::ColorType::Rgba8,
+                )
+                .with_context(|| format!("Failed to save texture {:x}", t.hashcode))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Loads a file previously saved with [`UXGeoFile::save`]
+    pub fn load<P: AsRef<Path>>(folder: P) -> anyhow::Result<Self> {
+        let folder = folder.as_ref();
+        let reader = BufReader::new(File::open(folder.join(GEOFILE_YAML))?);
+        let mut file: UXGeoFile = serde_yaml::from_reader(reader)?;
+
+        let texture_folder = folder.join(TEXTURE_DIR);
+        for t in file.textures.iter_mut() {
+            for i in 0..t.frame_count {
+                let path = texture_folder.join(texture_frame_filename(t.hashcode, i));
+                let frame = image::open(&path)
+                    .with_context(|| format!("Failed to load {}", path.to_string_lossy()))?
+                    .into_rgba8();
+
+                t.frames.push(frame.into_raw());
+            }
+        }
+
+        Ok(file)
+    }
+}
+
+fn texture_frame_filename(hashcode: Hashcode, frame: usize) -> String {
+    format!("{hashcode:08x}_frame{frame}.png")
+}
+
+impl From<&EXGeoPath> for UXGeoFilePath {
+    fn from(p: &EXGeoPath) -> Self {
+        Self {
+            hashcode: p.hashcode,
+            position: p.position,
+            flags: p.flags,
+            path_type: p.ptype,
+            bounds_box: p.bounds_box,
+        }
+    }
+}
+
+impl From<&EXGeoPlacement> for UXGeoFilePlacement {
+    fn from(p: &EXGeoPlacement) -> Self {
+        Self {
+            hashcode: p.hashcode,
+            object_ref: p.object_ref,
+            position: p.position,
+            rotation: p.rotation,
+            scale: p.scale,
+            flags: p.flags,
+            engine_flags: p.engine_flags,
+            map_on: p.map_on,
+            light_set: p.light_set,
+            group: p.group,
+        }
+    }
+}
+
+impl From<&EXGeoLight> for UXGeoFileLight {
+    fn from(l: &EXGeoLight) -> Self {
+        Self {
+            hashcode: l.hashcode,
+            position: l.position,
+            beam: l.beam,
+            flags: l.flags,
+            light_type: l.ltype,
+            beam_angle: l.beam_angle,
+            colour: l.colour,
+            radius: l.radius,
+            max_effect_fraction: l.max_effect_fraction,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn save_load_roundtrip() {
+        let file = UXGeoFile {
+            hashcode: 0x1d000001,
+            version: 259,
+            flags: 0,
+            platform: "pc".to_string(),
+            hashcode_names: [(0x1d000001, "HT_File_Test".to_string())].into(),
+            external_references: vec![(0x1d000002, 0x06000001)],
+            entities: vec![],
+            textures: vec![UXGeoFileTexture {
+                hashcode: 0x86000000,
+                width: 2,
+                height: 2,
+                depth: 1,
+                flags: 0,
+                game_flags: 0,
+                scroll: [1, -1],
+                framerate: 15,
+                color: [255, 128, 64, 255],
+                external_texture: None,
+                frames: vec![(0..16).collect(), (16..32).collect()],
+                frame_count: 2,
+            }],
+            maps: vec![UXGeoFileMap {
+                hashcode: 0x9c000000,
+                paths: vec![],
+                placements: vec![UXGeoFilePlacement {
+                    hashcode: 0x9e000001,
+                    object_ref: 0x82000003,
+                    position: [1.0, 2.0, 3.0],
+                    rotation: [0.0, 0.5, 0.0],
+                    scale: [1.0; 3],
+                    flags: 4,
+                    engine_flags: 0,
+                    map_on: 1,
+                    light_set: 0,
+                    group: -1,
+                }],
+                lights: vec![],
+                triggers: vec![],
+            }],
+            scripts: vec![],
+            spreadsheets: vec![],
+            anim_skins: vec![UXGeoFileAnimSkin {
+                hashcode: 0x83000000,
+                bone_count: 1,
+                bone_rotations: vec![[0.0, 0.0, 0.0, 1.0]],
+                hierarchy: vec![(0, 1, 0)],
+                entities: vec![(0, 0, -1)],
+            }],
+        };
+
+        let folder = std::env::temp_dir().join(format!("eurochef-geofile-{}", std::process::id()));
+        file.save(&folder).unwrap();
+        let loaded = UXGeoFile::load(&folder).unwrap();
+        std::fs::remove_dir_all(&folder).unwrap();
+
+        assert_eq!(
+            serde_yaml::to_string(&loaded).unwrap(),
+            serde_yaml::to_string(&file).unwrap()
+        );
+        assert_eq!(loaded.textures[0].frames, file.textures[0].frames);
+    }
+}
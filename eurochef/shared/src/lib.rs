@@ -2,6 +2,7 @@ use anyhow::anyhow;
 
 pub mod entities;
 pub mod filesystem;
+pub mod geofile;
 pub mod hashcodes;
 pub mod maps;
 pub mod platform;
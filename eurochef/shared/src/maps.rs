@@ -4,7 +4,7 @@ use eurochef_edb::Hashcode;
 use nohash_hasher::IntMap;
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct UXGeoTrigger {
     // TODO(cohae): Is this related to a refptr?
     pub link_ref: i32,
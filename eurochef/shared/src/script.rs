@@ -1,16 +1,19 @@
 use std::{io::Seek, ops::Range};
 
+use serde::{Deserialize, Serialize};
+
 use eurochef_edb::{
     binrw::{BinReaderExt, BinResult, Endian},
     common::EXRelPtr,
     edb::EdbFile,
     error::Result,
     header::EXGeoAnimScriptHeader,
+    references::record_reference,
     script::{EXGeoAnimScript, EXGeoAnimScriptControllerChannels, EXGeoAnimScriptControllerHeader},
     Hashcode,
 };
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum UXGeoScriptCommandData {
     Entity {
         hashcode: Hashcode,
@@ -43,7 +46,7 @@ pub enum UXGeoScriptCommandData {
     },
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UXGeoScriptCommand {
     pub start: i16,
     pub length: u16,
@@ -60,7 +63,7 @@ impl UXGeoScriptCommand {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UXGeoScript {
     pub hashcode: Hashcode,
     pub framerate: f32,
@@ -97,7 +100,12 @@ impl UXGeoScript {
         Ok(res)
     }
 
+    /// Reads a script, adding the objects used by its commands to the references of `edb`
     pub fn read(header: &EXGeoAnimScriptHeader, edb: &mut EdbFile) -> Result<UXGeoScript> {
+        edb.collect_references(|edb| Self::read_inner(header, edb))
+    }
+
+    fn read_inner(header: &EXGeoAnimScriptHeader, edb: &mut EdbFile) -> Result<UXGeoScript> {
         edb.seek(std::io::SeekFrom::Start(header.address as u64))?;
         let script = edb.read_type::<EXGeoAnimScript>(edb.endian)?;
 
@@ -139,7 +147,7 @@ impl UXGeoScript {
                 UXGeoScriptCommandData::Entity { hashcode, file }
                 | UXGeoScriptCommandData::Particle { hashcode, file }
                 | UXGeoScriptCommandData::SubScript { hashcode, file } => {
-                    edb.add_reference(*file, *hashcode)
+                    record_reference(*file, *hashcode)
                 }
                 UXGeoScriptCommandData::Animation {
                     skin_file,
@@ -147,8 +155,8 @@ impl UXGeoScript {
                     anim_file,
                     anim_hashcode,
                 } => {
-                    edb.add_reference(*skin_file, *skin_hashcode);
-                    edb.add_reference(*anim_file, *anim_hashcode);
+                    record_reference(*skin_file, *skin_hashcode);
+                    record_reference(*anim_file, *anim_hashcode);
                 }
                 _ => {}
             };
@@ -1,6 +1,6 @@
 use anyhow::Context;
 use nohash_hasher::IntMap;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::io::{Seek, SeekFrom, Write};
 
 use crate::maps::DefinitionDataType;
@@ -13,13 +13,13 @@ use eurochef_edb::{
 };
 use tracing::warn;
 
-#[derive(Clone)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum UXGeoSpreadsheet {
     Data(Vec<UXGeoDataSheet>),
     Text(Vec<UXGeoTextSection>),
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct UXGeoDataSheet {
     pub row_count: u32,
     // We can't find the file size from here, so we just supply an offset
@@ -27,13 +27,13 @@ pub struct UXGeoDataSheet {
     pub address: u32,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct UXGeoTextSection {
     pub hashcode: u32,
     pub entries: Vec<UXGeoTextItem>,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct UXGeoTextItem {
     pub hashcode: u32,
     pub text: String,
@@ -61,7 +61,9 @@ impl UXGeoSpreadsheet {
                             hashcode: s.hashcode,
                             entries: vec![],
                         };
-                        let refpointer = &edb.header.refpointer_list[s.refpointer_index as usize];
+                        let refpointer = edb
+                            .refpointer(s.refpointer_index as usize)
+                            .context("Failed to resolve text section")?;
 
                         edb.seek(SeekFrom::Start(refpointer.address as u64))
                             .unwrap();
@@ -93,12 +93,16 @@ impl UXGeoTexture {
     ) -> anyhow::Result<Self> {
         edb.seek(std::io::SeekFrom::Start(address as u64))?;
         let tex = edb
-            .read_type_args::<EXGeoTexture>(edb.endian, (edb.header.version, edb.platform))
+            .collect_references(|edb| {
+                edb.read_type_args::<EXGeoTexture>(
+                    edb.endian,
+                    (edb.header.version, edb.platform, edb.profile),
+                )
+            })
             .context("Failed to read texture")?;
 
         if let Some(external_file) = tex.external_file {
             let external_texture = tex.frame_offsets[0].offset_relative() as u32;
-            edb.add_reference(external_file, external_texture);
             return Ok(UXGeoTexture {
                 width: tex.width,
                 height: tex.height,